@@ -155,7 +155,7 @@ async fn main() -> anyhow::Result<()> {
             }
 
             // Check we have a device matching the index specified
-            if args.index > devices.len() {
+            if args.index >= devices.len() {
                 return Err(anyhow::Error::from(Error::InvalidDeviceIndex(args.index)));
             }
 
@@ -237,7 +237,7 @@ async fn connect(
     }
 
     // Check we have a device matching the index specified
-    if index > devices.len() {
+    if index >= devices.len() {
         return Err(Error::InvalidDeviceIndex(index));
     }
 
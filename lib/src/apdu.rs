@@ -0,0 +1,122 @@
+//! APDU helpers, re-exporting the [ApduCommand] builder and [chunk_apdu] helper
+//! from [ledger_proto], response parsing (see [parse_response]) and status word
+//! descriptions (see [status_message])
+
+use ledger_proto::StatusCode;
+pub use ledger_proto::{chunk_apdu, ApduCommand};
+
+use crate::{check_response, status_error, Error};
+
+/// APDU response, split into data and status word
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ApduResponse {
+    /// Response data (excluding the status word)
+    pub data: Vec<u8>,
+    /// Status word
+    pub sw: u16,
+}
+
+impl ApduResponse {
+    /// Check whether the response status word indicates success (`0x9000`)
+    pub fn is_success(&self) -> bool {
+        self.sw == StatusCode::Ok as u16
+    }
+
+    /// Fetch response data, returning an error for non-success status words
+    /// (see [Exchange::exchange_checked](crate::Exchange::exchange_checked))
+    pub fn into_result(self) -> Result<Vec<u8>, Error> {
+        match self.is_success() {
+            true => Ok(self.data),
+            false => {
+                let [sw1, sw2] = self.sw.to_be_bytes();
+                Err(status_error(sw1, sw2))
+            }
+        }
+    }
+}
+
+/// Parse a raw response (as returned by [Exchange::exchange](crate::Exchange::exchange))
+/// into an [ApduResponse], returning [Error::UnexpectedResponse] if the status word is missing
+pub fn parse_response(resp: &[u8]) -> Result<ApduResponse, Error> {
+    check_response(resp)?;
+
+    let (data, sw) = resp.split_at(resp.len() - 2);
+
+    Ok(ApduResponse {
+        data: data.to_vec(),
+        sw: u16::from_be_bytes([sw[0], sw[1]]),
+    })
+}
+
+/// Human-readable messages for common status words
+const STATUS_MESSAGES: &[(u16, &str)] = &[
+    (0x6985, "User denied the request"),
+    (0x5515, "Device is locked"),
+    (0x6a80, "Invalid data"),
+    (0x6e00, "Application not open"),
+    (0x6d00, "Instruction not supported"),
+    (0x6b00, "Invalid P1 / P2 parameters"),
+    (0x6700, "Incorrect length"),
+    (0x6982, "Security status not satisfied"),
+    (0x6807, "Application not installed"),
+];
+
+/// Fetch a human-readable message for common status words
+pub fn status_message(sw: u16) -> Option<&'static str> {
+    STATUS_MESSAGES
+        .iter()
+        .find(|(s, _)| *s == sw)
+        .map(|(_, m)| *m)
+}
+
+/// Describe a status word, using [status_message] or the matching [StatusCode]
+/// where available, and rendering as `APDU error 0xXXXX` otherwise
+pub fn describe_status(sw: u16) -> String {
+    if let Some(m) = status_message(sw) {
+        return format!("{m} (0x{sw:04x})");
+    }
+
+    match StatusCode::try_from(sw) {
+        Ok(c) => format!("{c} (0x{sw:04x})"),
+        Err(_) => format!("APDU error 0x{sw:04x}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_status_words() {
+        assert_eq!(describe_status(0x6985), "User denied the request (0x6985)");
+        assert_eq!(describe_status(0x5515), "Device is locked (0x5515)");
+        assert_eq!(describe_status(0x9000), "OK (0x9000)");
+        assert_eq!(describe_status(0x1234), "APDU error 0x1234");
+    }
+
+    #[test]
+    fn parse_responses() {
+        let r = parse_response(&[0x01, 0x02, 0x90, 0x00]).unwrap();
+        assert_eq!(
+            r,
+            ApduResponse {
+                data: vec![0x01, 0x02],
+                sw: 0x9000
+            }
+        );
+        assert!(r.is_success());
+        assert_eq!(r.into_result().unwrap(), vec![0x01, 0x02]);
+
+        let r = parse_response(&[0x6e, 0x00]).unwrap();
+        assert!(r.data.is_empty());
+        assert!(matches!(
+            r.into_result(),
+            Err(Error::Status(StatusCode::ClaNotSupported))
+        ));
+
+        assert!(matches!(
+            parse_response(&[0x90]),
+            Err(Error::UnexpectedResponse)
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+//! Synchronous (blocking) wrappers over [Transport] and [Exchange] implementations,
+//! for use from non-async applications
+//!
+//! These wrap async calls using a small internal single-threaded `tokio` runtime,
+//! and _must not_ be called from within an async context.
+//!
+//! ```no_run
+//! use ledger_lib::{blocking::BlockingTransport, Filters, DEFAULT_TIMEOUT};
+//!
+//! let mut t = BlockingTransport::generic()?;
+//!
+//! let devices = t.list(Filters::Any)?;
+//! let mut d = t.connect(devices[0].clone())?;
+//!
+//! let info = d.app_info(DEFAULT_TIMEOUT)?;
+//! println!("info: {info:?}");
+//! # Ok::<(), ledger_lib::Error>(())
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    info::{AppInfo, DeviceInfo},
+    transport::GenericTransport,
+    Device, Error, Exchange, LedgerInfo, Transport,
+};
+
+/// Blocking wrapper for [Transport] implementations
+pub struct BlockingTransport<T> {
+    rt: Arc<Runtime>,
+    t: T,
+}
+
+/// Blocking wrapper for [Exchange] implementations
+pub struct BlockingDevice<D> {
+    rt: Arc<Runtime>,
+    d: D,
+}
+
+/// Helper to create the internal runtime
+fn runtime() -> Result<Arc<Runtime>, Error> {
+    let rt = Builder::new_current_thread().enable_all().build()?;
+    Ok(Arc::new(rt))
+}
+
+impl BlockingTransport<GenericTransport> {
+    /// Create a new [BlockingTransport] using a [GenericTransport] over all enabled transports
+    pub fn generic() -> Result<Self, Error> {
+        let rt = runtime()?;
+        let t = rt.block_on(GenericTransport::new())?;
+
+        Ok(Self { rt, t })
+    }
+}
+
+impl<T: Transport> BlockingTransport<T> {
+    /// Create a new [BlockingTransport] wrapping the provided transport
+    pub fn new(t: T) -> Result<Self, Error> {
+        Ok(Self { rt: runtime()?, t })
+    }
+
+    /// List available devices
+    pub fn list(&mut self, filters: T::Filters) -> Result<Vec<LedgerInfo>, Error> {
+        self.rt.block_on(self.t.list(filters))
+    }
+
+    /// Connect to a device using info from a previous list operation
+    pub fn connect(&mut self, info: T::Info) -> Result<BlockingDevice<T::Device>, Error> {
+        let d = self.rt.block_on(self.t.connect(info))?;
+
+        Ok(BlockingDevice {
+            rt: self.rt.clone(),
+            d,
+        })
+    }
+}
+
+impl<D: Exchange + Send> BlockingDevice<D> {
+    /// Create a new [BlockingDevice] wrapping the provided device
+    pub fn new(d: D) -> Result<Self, Error> {
+        Ok(Self { rt: runtime()?, d })
+    }
+
+    /// Exchange a raw APDU with the device, see [Exchange::exchange]
+    pub fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        self.rt.block_on(self.d.exchange(command, timeout))
+    }
+
+    /// Fetch application information, see [Device::app_info]
+    pub fn app_info(&mut self, timeout: Duration) -> Result<AppInfo, Error> {
+        self.rt.block_on(self.d.app_info(timeout))
+    }
+
+    /// Fetch device information, see [Device::device_info]
+    pub fn device_info(&mut self, timeout: Duration) -> Result<DeviceInfo, Error> {
+        self.rt.block_on(self.d.device_info(timeout))
+    }
+
+    /// Fetch the underlying device
+    pub fn into_inner(self) -> D {
+        self.d
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::EchoExchange, DEFAULT_TIMEOUT};
+
+    #[test]
+    fn blocking_exchange() {
+        let mut d = BlockingDevice::new(EchoExchange).unwrap();
+
+        let r = d.exchange(&[0x01, 0x02], DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(r, vec![0x01, 0x02, 0x90, 0x00]);
+    }
+}
@@ -2,17 +2,19 @@
 
 use std::time::Duration;
 
+use crate::trace::{debug, error};
 use encdec::{EncDec, Encode};
-use tracing::{debug, error};
 
 use ledger_proto::{
     apdus::{AppInfoReq, AppInfoResp, DeviceInfoReq, DeviceInfoResp},
-    ApduError, ApduReq, StatusCode,
+    ApduError, ApduReq,
 };
 
 use crate::{
-    info::{AppInfo, DeviceInfo},
-    Error, Exchange,
+    check_response,
+    info::{AppInfo, Context, DeviceInfo},
+    logging::log_redaction,
+    status_error, Error, Exchange,
 };
 
 const APDU_BUFF_LEN: usize = 256;
@@ -36,11 +38,16 @@ pub trait Device {
             .request::<AppInfoResp>(AppInfoReq {}, &mut buff[..], timeout)
             .await?;
 
-        Ok(AppInfo {
-            name: r.name.to_string(),
-            version: r.version.to_string(),
-            flags: r.flags,
-        })
+        Ok(r.into())
+    }
+
+    /// Fetch the current device context (dashboard or running application)
+    ///
+    /// This allows callers to check whether dashboard or application commands
+    /// are available prior to issuing them.
+    async fn current_context(&mut self, timeout: Duration) -> Result<Context, Error> {
+        let i = self.app_info(timeout).await?;
+        Ok(i.into())
     }
 
     /// Fetch device information
@@ -51,12 +58,7 @@ pub trait Device {
             .request::<DeviceInfoResp>(DeviceInfoReq {}, &mut buff[..], timeout)
             .await?;
 
-        Ok(DeviceInfo {
-            target_id: r.target_id,
-            se_version: r.se_version.to_string(),
-            mcu_version: r.mcu_version.to_string(),
-            flags: r.flags.to_vec(),
-        })
+        Ok(r.into())
     }
 }
 
@@ -70,7 +72,9 @@ impl<T: Exchange + Send> Device for T {
         buff: &'b mut [u8],
         timeout: Duration,
     ) -> Result<RESP, Error> {
-        debug!("TX: {req:?}");
+        if !log_redaction() {
+            debug!("TX: {req:?}");
+        }
 
         // Encode request
         let n = encode_request(req, buff)?;
@@ -91,20 +95,21 @@ impl<T: Exchange + Send> Device for T {
         }
         buff[..n].copy_from_slice(&resp_bytes[..]);
 
+        // Check the response includes a status word
+        check_response(&resp_bytes)?;
+
         // Handle error responses (2 bytes long, only a status)
         if n == 2 {
             // Return status code if matched, unknown otherwise
-            let v = u16::from_be_bytes([resp_bytes[0], resp_bytes[1]]);
-            match StatusCode::try_from(v) {
-                Ok(c) => return Err(Error::Status(c)),
-                Err(_) => return Err(Error::UnknownStatus(resp_bytes[0], resp_bytes[1])),
-            }
+            return Err(status_error(resp_bytes[0], resp_bytes[1]));
         }
 
         // Decode response data - status bytes
         let (resp, _) = RESP::decode(&buff[..n - 2])?;
 
-        debug!("RX: {resp:?}");
+        if !log_redaction() {
+            debug!("RX: {resp:?}");
+        }
 
         // Return decode response
         Ok(resp)
@@ -145,7 +150,18 @@ fn encode_request<'a, REQ: ApduReq<'a>>(req: REQ, buff: &mut [u8]) -> Result<usi
 mod tests {
     use ledger_proto::{apdus::AppInfoReq, ApduStatic};
 
-    use super::encode_request;
+    use super::*;
+    use crate::{mock::ScriptedExchange, DEFAULT_TIMEOUT};
+
+    #[tokio::test]
+    async fn missing_status_word() {
+        for r in [vec![], vec![0x90]] {
+            let mut d = ScriptedExchange::new(vec![r]);
+
+            let e = d.app_info(DEFAULT_TIMEOUT).await;
+            assert!(matches!(e, Err(Error::UnexpectedResponse)));
+        }
+    }
 
     #[test]
     fn test_encode_requests() {
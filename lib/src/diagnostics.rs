@@ -0,0 +1,133 @@
+//! Diagnostic helpers for measuring device performance
+
+use std::time::{Duration, Instant};
+
+use ledger_proto::{apdus::AppInfoReq, ApduError, ApduStatic};
+
+use crate::{Error, Exchange, DEFAULT_TIMEOUT};
+
+/// Round-trip benchmark results, see [benchmark]
+#[derive(Clone, PartialEq, Debug)]
+pub struct BenchReport {
+    /// Number of exchanges performed
+    pub rounds: usize,
+    /// Request payload size in bytes
+    pub payload_size: usize,
+    /// Transport MTU in use (where applicable)
+    pub mtu: Option<u8>,
+    /// Minimum round-trip time
+    pub min: Duration,
+    /// Average round-trip time
+    pub avg: Duration,
+    /// Maximum round-trip time
+    pub max: Duration,
+    /// Effective throughput (request and response bytes) in bytes per second
+    pub bytes_per_sec: f64,
+}
+
+impl BenchReport {
+    /// Build a report from per-round samples and the total bytes exchanged
+    fn from_samples(payload_size: usize, samples: &[Duration], total_bytes: usize) -> Self {
+        let total: Duration = samples.iter().sum();
+
+        let bytes_per_sec = match total.is_zero() {
+            true => 0.0,
+            false => total_bytes as f64 / total.as_secs_f64(),
+        };
+
+        Self {
+            rounds: samples.len(),
+            payload_size,
+            mtu: None,
+            min: samples.iter().min().copied().unwrap_or_default(),
+            avg: total.checked_div(samples.len() as u32).unwrap_or_default(),
+            max: samples.iter().max().copied().unwrap_or_default(),
+            bytes_per_sec,
+        }
+    }
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} rounds of {} bytes: min {:?} avg {:?} max {:?} ({:.1} B/s)",
+            self.rounds, self.payload_size, self.min, self.avg, self.max, self.bytes_per_sec
+        )?;
+
+        if let Some(mtu) = self.mtu {
+            write!(f, " MTU: {mtu}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Benchmark round-trip exchanges with a device
+///
+/// This repeatedly issues a benign [AppInfoReq] padded with `payload_size` bytes of data,
+/// measuring response times. Devices may reject the padded request with an error status,
+/// this is still a complete round-trip and is included in the results.
+pub async fn benchmark(
+    d: &mut (impl Exchange + Send),
+    payload_size: usize,
+    rounds: usize,
+) -> Result<BenchReport, Error> {
+    // Short APDUs are limited to 255 bytes of data
+    if payload_size > u8::MAX as usize {
+        return Err(ApduError::InvalidLength.into());
+    }
+
+    // Setup padded request
+    let mut req = vec![
+        AppInfoReq::CLA,
+        AppInfoReq::INS,
+        0x00,
+        0x00,
+        payload_size as u8,
+    ];
+    req.resize(5 + payload_size, 0);
+
+    let mut samples = Vec::with_capacity(rounds);
+    let mut total_bytes = 0;
+
+    for _ in 0..rounds {
+        let start = Instant::now();
+        let resp = d.exchange(&req, DEFAULT_TIMEOUT).await?;
+        samples.push(start.elapsed());
+
+        total_bytes += req.len() + resp.len();
+    }
+
+    Ok(BenchReport::from_samples(
+        payload_size,
+        &samples,
+        total_bytes,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bench_report() {
+        let samples = [
+            Duration::from_millis(10),
+            Duration::from_millis(30),
+            Duration::from_millis(20),
+        ];
+
+        let r = BenchReport::from_samples(16, &samples, 600);
+        assert_eq!(r.rounds, 3);
+        assert_eq!(r.min, Duration::from_millis(10));
+        assert_eq!(r.avg, Duration::from_millis(20));
+        assert_eq!(r.max, Duration::from_millis(30));
+        assert_eq!(r.bytes_per_sec, 10_000.0);
+
+        let r = BenchReport::from_samples(16, &[], 0);
+        assert_eq!(r.rounds, 0);
+        assert_eq!(r.avg, Duration::ZERO);
+        assert_eq!(r.bytes_per_sec, 0.0);
+    }
+}
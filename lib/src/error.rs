@@ -2,6 +2,11 @@
 
 use ledger_proto::{ApduError, StatusCode};
 
+use crate::{
+    apdu::describe_status,
+    info::{ConnType, Model},
+};
+
 /// Ledger interface error type
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -9,23 +14,47 @@ pub enum Error {
     #[error(transparent)]
     Hid(#[from] hidapi::HidError),
 
+    #[error("IO error: {0}")]
+    Io(#[source] std::io::Error),
+
+    /// Retained for compatibility, IO errors are now reported as [Error::Io]
+    /// (or [Error::Timeout] / [Error::Closed]) and this is no longer returned
     #[cfg(feature = "transport_tcp")]
+    #[deprecated(
+        note = "IO errors are reported as `Error::Io`, `Error::Timeout`, or `Error::Closed`"
+    )]
     #[error(transparent)]
-    Tcp(#[from] tokio::io::Error),
+    Tcp(tokio::io::Error),
 
     #[cfg(feature = "transport_ble")]
     #[error(transparent)]
-    Ble(#[from] btleplug::Error),
+    Ble(btleplug::Error),
 
     #[error("Unknown ledger model: {0}")]
     UnknownModel(u16),
 
+    /// Device model is not supported by the transport (eg. BLE connection to a Nano S)
+    #[error("{0} not supported by this transport")]
+    Unsupported(Model),
+
     #[error("Unknown error")]
     Unknown,
 
     #[error("No devices found")]
     NoDevices,
 
+    /// Required BLE characteristic(s) not found during service discovery
+    #[error("BLE characteristic not found")]
+    CharacteristicNotFound,
+
+    /// Bluetooth is unavailable (no adapters, or adapter powered off)
+    #[error("Bluetooth unavailable")]
+    BluetoothUnavailable,
+
+    /// Bluetooth access has not been granted to the application
+    #[error("Bluetooth unauthorized")]
+    BluetoothUnauthorized,
+
     #[error("Invalid device index: {0}")]
     InvalidDeviceIndex(usize),
 
@@ -33,19 +62,35 @@ pub enum Error {
     Apdu(#[from] ApduError),
 
     /// Recognised status codes (see [StatusCode])
-    #[error("Status: {0}")]
+    #[error("{}", describe_status(*.0 as u16))]
     Status(StatusCode),
 
     /// Unrecognised status codes
-    #[error("Status: 0x{0:02x}{1:02x} (unrecognised)")]
+    #[error("{}", describe_status(u16::from_be_bytes([*.0, *.1])))]
     UnknownStatus(u8, u8),
 
+    /// Device is locked and must be unlocked by the user ([StatusCode::LockedDevice])
+    #[error("{}", describe_status(StatusCode::LockedDevice as u16))]
+    DeviceLocked,
+
     #[error("Request timeout")]
     Timeout,
 
+    #[error("Connection timeout during {0}")]
+    ConnectTimeout(&'static str),
+
+    #[error("Request cancelled")]
+    Cancelled,
+
+    #[error("Request timeout with incomplete response ({} of {expected} bytes)", received.len())]
+    IncompleteResponse { received: Vec<u8>, expected: usize },
+
     #[error("Device or transport closed")]
     Closed,
 
+    #[error("Response length {len} exceeds maximum ({max})")]
+    ResponseTooLarge { len: usize, max: usize },
+
     #[error("Empty response payload")]
     EmptyResponse,
 
@@ -55,12 +100,117 @@ pub enum Error {
     #[error("Device in use")]
     DeviceInUse,
 
+    #[error("Device already connected")]
+    AlreadyConnected,
+
+    #[error("Connection type mismatch (expected {expected:?}, found {found:?})")]
+    ConnTypeMismatch { expected: ConnType, found: ConnType },
+
     #[error("Already running application ({0})")]
     ApplicationLoaded(String),
 }
 
+impl Error {
+    /// Fetch the status word associated with an error, where this was returned by the device
+    pub fn status_word(&self) -> Option<u16> {
+        match self {
+            Error::Status(c) => Some(c.code()),
+            Error::UnknownStatus(a, b) => Some(u16::from_be_bytes([*a, *b])),
+            Error::DeviceLocked => Some(StatusCode::LockedDevice.code()),
+            _ => None,
+        }
+    }
+}
+
+/// Convert IO errors, mapping timeouts to [Error::Timeout] and dropped
+/// connections to [Error::Closed]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        use std::io::ErrorKind::*;
+
+        match e.kind() {
+            WouldBlock | TimedOut => Self::Timeout,
+            ConnectionReset | ConnectionAborted | BrokenPipe | NotConnected | UnexpectedEof => {
+                Self::Closed
+            }
+            _ => Self::Io(e),
+        }
+    }
+}
+
+/// Convert BLE errors, mapping permission failures to [Error::BluetoothUnauthorized]
+#[cfg(feature = "transport_ble")]
+impl From<btleplug::Error> for Error {
+    fn from(e: btleplug::Error) -> Self {
+        match e {
+            btleplug::Error::PermissionDenied => Self::BluetoothUnauthorized,
+            _ => Self::Ble(e),
+        }
+    }
+}
+
+impl From<std::convert::Infallible> for Error {
+    fn from(e: std::convert::Infallible) -> Self {
+        match e {}
+    }
+}
+
 impl From<tokio::time::error::Elapsed> for Error {
     fn from(_e: tokio::time::error::Elapsed) -> Self {
         Self::Timeout
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn io_error_conversion() {
+        let tests = [
+            (ErrorKind::TimedOut, Error::Timeout),
+            (ErrorKind::WouldBlock, Error::Timeout),
+            (ErrorKind::ConnectionReset, Error::Closed),
+            (ErrorKind::UnexpectedEof, Error::Closed),
+        ];
+
+        for (kind, expected) in tests {
+            let e = Error::from(std::io::Error::from(kind));
+            assert_eq!(e.to_string(), expected.to_string(), "{kind:?}");
+        }
+
+        let e = Error::from(std::io::Error::from(ErrorKind::PermissionDenied));
+        assert!(matches!(e, Error::Io(_)));
+        assert!(std::error::Error::source(&e).is_some());
+    }
+
+    #[test]
+    fn status_display() {
+        let e = Error::Status(StatusCode::ConditionsOfUseNotSatisfied);
+        assert_eq!(e.to_string(), "User denied the request (0x6985)");
+
+        let e = Error::UnknownStatus(0x12, 0x34);
+        assert_eq!(e.to_string(), "APDU error 0x1234");
+        assert_eq!(e.status_word(), Some(0x1234));
+
+        let e = Error::DeviceLocked;
+        assert_eq!(e.to_string(), "Device is locked (0x5515)");
+        assert_eq!(e.status_word(), Some(0x5515));
+
+        let e = Error::Unsupported(Model::NanoS);
+        assert_eq!(e.to_string(), "NanoS not supported by this transport");
+        assert_eq!(e.status_word(), None);
+    }
+
+    #[cfg(feature = "transport_ble")]
+    #[test]
+    fn ble_error_conversion() {
+        let e = Error::from(btleplug::Error::PermissionDenied);
+        assert!(matches!(e, Error::BluetoothUnauthorized));
+
+        let e = Error::from(btleplug::Error::NotConnected);
+        assert!(matches!(e, Error::Ble(btleplug::Error::NotConnected)));
+    }
+}
@@ -1,28 +1,76 @@
 //! Device information types and connection filters
 
-use strum::{Display, EnumString};
+use encdec::Decode;
+use ledger_proto::apdus::{AppInfoResp, DeviceInfoResp};
+pub use ledger_proto::Model;
 
-use crate::Filters;
+use crate::{Error, Filters};
 
 use super::transport;
 
 /// Ledger device information
-#[derive(Clone, PartialEq, Debug)]
+///
+/// Equality and hashing ignore the user-assigned [LedgerInfo::label].
+#[derive(Clone, Debug)]
 pub struct LedgerInfo {
     /// Device Model
     pub model: Model,
 
     /// Device connection information
     pub conn: ConnInfo,
+
+    /// User-assigned device label, see [LedgerInfo::stable_id] for persistence
+    pub label: Option<String>,
+}
+
+impl PartialEq for LedgerInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.model == other.model && self.conn == other.conn
+    }
+}
+
+impl Eq for LedgerInfo {}
+
+impl std::hash::Hash for LedgerInfo {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.model.hash(state);
+        self.conn.hash(state);
+    }
 }
 
 impl std::fmt::Display for LedgerInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} ({})", self.model, self.conn)
+        match &self.label {
+            Some(l) => write!(f, "{} ({})", l, self.conn),
+            None => write!(f, "{} ({})", self.model, self.conn),
+        }
     }
 }
 
 impl LedgerInfo {
+    /// Set a user-assigned label, displayed in place of the device model
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Fetch an identifier for this device that is stable across listings,
+    /// for persisting labels or other per-device settings
+    ///
+    /// This is the serial number for USB devices (`None` where unavailable),
+    /// the socket address for TCP, and the platform peripheral ID for BLE (falling back
+    /// to the device address, or `None` where neither is available).
+    pub fn stable_id(&self) -> Option<String> {
+        match &self.conn {
+            #[cfg(feature = "transport_usb")]
+            ConnInfo::Usb(i) => i.serial.clone(),
+            #[cfg(feature = "transport_tcp")]
+            ConnInfo::Tcp(i) => Some(i.addr.to_string()),
+            #[cfg(feature = "transport_ble")]
+            ConnInfo::Ble(i) => i.stable_id(),
+        }
+    }
+
     /// Fetch connection kind enumeration
     pub fn kind(&self) -> ConnType {
         match &self.conn {
@@ -36,40 +84,8 @@ impl LedgerInfo {
     }
 }
 
-/// Ledger device models
-#[derive(Clone, PartialEq, Debug, Display, EnumString)]
-pub enum Model {
-    /// Nano S
-    NanoS,
-    /// Nano S Plus
-    NanoSPlus,
-    /// Nano X
-    NanoX,
-    /// Stax
-    Stax,
-    /// Unknown model
-    Unknown(u16),
-}
-
-impl Model {
-    /// Convert a USB PID to a [Model] kind
-    ///
-    /// Note that ledger PIDs vary depending on the device state so only the top byte is used
-    /// for matching.
-    pub fn from_pid(pid: u16) -> Model {
-        match pid & 0xFF00 {
-            // TODO: support all the models
-            //0x0001 => Ok(Model::NanoS),
-            0x4000 => Model::NanoX,
-            0x5000 => Model::NanoSPlus,
-            //0x0006 => Ok(Model::Stax),
-            _ => Model::Unknown(pid),
-        }
-    }
-}
-
 /// Ledger connection information
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum ConnInfo {
     #[cfg(feature = "transport_usb")]
     Usb(transport::UsbInfo),
@@ -79,8 +95,40 @@ pub enum ConnInfo {
     Ble(transport::BleInfo),
 }
 
+impl ConnInfo {
+    /// Fetch USB connection info, returning `None` for other connection types
+    #[cfg(feature = "transport_usb")]
+    pub fn as_usb(&self) -> Option<&transport::UsbInfo> {
+        #[allow(unreachable_patterns)]
+        match self {
+            Self::Usb(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Fetch TCP connection info, returning `None` for other connection types
+    #[cfg(feature = "transport_tcp")]
+    pub fn as_tcp(&self) -> Option<&transport::TcpInfo> {
+        #[allow(unreachable_patterns)]
+        match self {
+            Self::Tcp(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Fetch BLE connection info, returning `None` for other connection types
+    #[cfg(feature = "transport_ble")]
+    pub fn as_ble(&self) -> Option<&transport::BleInfo> {
+        #[allow(unreachable_patterns)]
+        match self {
+            Self::Ble(i) => Some(i),
+            _ => None,
+        }
+    }
+}
+
 /// Ledger connection types
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum ConnType {
     Usb,
     Tcp,
@@ -98,6 +146,33 @@ impl From<ConnType> for Filters {
     }
 }
 
+/// Helper to extract transport-specific info from a [LedgerInfo]
+macro_rules! impl_try_from_info {
+    ($feature:literal, $variant:ident, $info:ty) => {
+        #[cfg(feature = $feature)]
+        impl TryFrom<LedgerInfo> for $info {
+            type Error = Error;
+
+            fn try_from(value: LedgerInfo) -> Result<Self, Self::Error> {
+                let found = value.kind();
+
+                #[allow(unreachable_patterns)]
+                match value.conn {
+                    ConnInfo::$variant(i) => Ok(i),
+                    _ => Err(Error::ConnTypeMismatch {
+                        expected: ConnType::$variant,
+                        found,
+                    }),
+                }
+            }
+        }
+    };
+}
+
+impl_try_from_info!("transport_usb", Usb, transport::UsbInfo);
+impl_try_from_info!("transport_tcp", Tcp, transport::TcpInfo);
+impl_try_from_info!("transport_ble", Ble, transport::BleInfo);
+
 impl std::fmt::Display for ConnInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -133,18 +208,256 @@ impl From<transport::BleInfo> for ConnInfo {
 }
 
 /// Application info object
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AppInfo {
     pub name: String,
     pub version: String,
     pub flags: ledger_proto::apdus::AppFlags,
 }
 
+impl AppInfo {
+    /// Check whether this info was reported by the dashboard (BOLOS) rather than an application
+    pub fn is_dashboard(&self) -> bool {
+        self.name == DASHBOARD_APP_NAME
+    }
+}
+
+/// Application name reported by the dashboard
+const DASHBOARD_APP_NAME: &str = "BOLOS";
+
+/// Current device context, see [Device::current_context](crate::Device::current_context)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Context {
+    /// Device is at the dashboard
+    Dashboard,
+    /// Application is running
+    App(AppInfo),
+}
+
+impl From<AppInfo> for Context {
+    fn from(i: AppInfo) -> Self {
+        match i.is_dashboard() {
+            true => Context::Dashboard,
+            false => Context::App(i),
+        }
+    }
+}
+
+impl From<AppInfoResp<'_>> for AppInfo {
+    fn from(r: AppInfoResp<'_>) -> Self {
+        Self {
+            name: r.name.to_string(),
+            version: r.version.to_string(),
+            flags: r.flags,
+        }
+    }
+}
+
+/// Parse [AppInfo] from an application info response body (excluding the status word)
+impl TryFrom<&[u8]> for AppInfo {
+    type Error = Error;
+
+    fn try_from(buff: &[u8]) -> Result<Self, Self::Error> {
+        let (r, _) = AppInfoResp::decode(buff).map_err(|_| Error::UnexpectedResponse)?;
+        Ok(r.into())
+    }
+}
+
 /// Device info object
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct DeviceInfo {
     pub target_id: [u8; 4],
     pub se_version: String,
     pub mcu_version: String,
     pub flags: Vec<u8>,
 }
+
+impl DeviceInfo {
+    /// Fetch the [Model] for the reported target ID, or `None` if this is not recognised
+    /// (see [Model::from_target_id])
+    pub fn target_model(&self) -> Option<Model> {
+        match Model::from_target_id(&self.target_id) {
+            Model::Unknown(_) => None,
+            m => Some(m),
+        }
+    }
+
+    /// Format the target ID as hex (eg. `0x33000004`)
+    pub fn target_id_hex(&self) -> String {
+        format!("0x{:08x}", u32::from_be_bytes(self.target_id))
+    }
+}
+
+impl From<DeviceInfoResp<'_>> for DeviceInfo {
+    fn from(r: DeviceInfoResp<'_>) -> Self {
+        Self {
+            target_id: r.target_id,
+            se_version: r.se_version.to_string(),
+            mcu_version: r.mcu_version.to_string(),
+            flags: r.flags.to_vec(),
+        }
+    }
+}
+
+/// Parse [DeviceInfo] from a device info response body (excluding the status word)
+impl TryFrom<&[u8]> for DeviceInfo {
+    type Error = Error;
+
+    fn try_from(buff: &[u8]) -> Result<Self, Self::Error> {
+        let (r, _) = DeviceInfoResp::decode(buff).map_err(|_| Error::UnexpectedResponse)?;
+        Ok(r.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger_proto::apdus::AppFlags;
+
+    use super::*;
+
+    /// Nano X dashboard application info response
+    const DASHBOARD_APP_INFO: &[u8] = &[
+        0x01, 0x05, b'B', b'O', b'L', b'O', b'S', 0x05, b'2', b'.', b'2', b'.', b'3',
+    ];
+
+    /// Ethereum application info response
+    const ETH_APP_INFO: &[u8] = &[
+        0x01, 0x08, b'E', b't', b'h', b'e', b'r', b'e', b'u', b'm', 0x06, b'1', b'.', b'1', b'0',
+        b'.', b'3', 0x01, 0x02,
+    ];
+
+    /// Nano X device info response
+    const NANOX_DEVICE_INFO: &[u8] = &[
+        0x33, 0x00, 0x00, 0x04, 0x05, b'2', b'.', b'2', b'.', b'3', 0x04, 0xe6, 0x00, 0x00, 0x00,
+        0x04, b'2', b'.', b'3', b'0',
+    ];
+
+    #[test]
+    fn parse_app_info() {
+        let i = AppInfo::try_from(DASHBOARD_APP_INFO).unwrap();
+        assert_eq!(
+            i,
+            AppInfo {
+                name: "BOLOS".to_string(),
+                version: "2.2.3".to_string(),
+                flags: AppFlags::empty(),
+            }
+        );
+
+        let i = AppInfo::try_from(ETH_APP_INFO).unwrap();
+        assert_eq!(
+            i,
+            AppInfo {
+                name: "Ethereum".to_string(),
+                version: "1.10.3".to_string(),
+                flags: AppFlags::SIGNED,
+            }
+        );
+    }
+
+    #[test]
+    fn app_context() {
+        let i = AppInfo::try_from(DASHBOARD_APP_INFO).unwrap();
+        assert!(i.is_dashboard());
+        assert_eq!(Context::from(i), Context::Dashboard);
+
+        let i = AppInfo::try_from(ETH_APP_INFO).unwrap();
+        assert!(!i.is_dashboard());
+        assert_eq!(Context::from(i.clone()), Context::App(i));
+    }
+
+    #[test]
+    fn parse_device_info() {
+        let i = DeviceInfo::try_from(NANOX_DEVICE_INFO).unwrap();
+        assert_eq!(
+            i,
+            DeviceInfo {
+                target_id: [0x33, 0x00, 0x00, 0x04],
+                se_version: "2.2.3".to_string(),
+                mcu_version: "2.30".to_string(),
+                flags: vec![0xe6, 0x00, 0x00, 0x00],
+            }
+        );
+
+        assert_eq!(i.target_model(), Some(Model::NanoX));
+        assert_eq!(i.target_id_hex(), "0x33000004");
+    }
+
+    #[test]
+    fn parse_truncated() {
+        assert!(matches!(
+            AppInfo::try_from(&ETH_APP_INFO[..5]),
+            Err(Error::UnexpectedResponse)
+        ));
+        assert!(matches!(
+            DeviceInfo::try_from(&NANOX_DEVICE_INFO[..12]),
+            Err(Error::UnexpectedResponse)
+        ));
+        // Truncated final field
+        assert!(matches!(
+            DeviceInfo::try_from(&NANOX_DEVICE_INFO[..NANOX_DEVICE_INFO.len() - 1]),
+            Err(Error::UnexpectedResponse)
+        ));
+        // Bad length prefix
+        assert!(matches!(
+            DeviceInfo::try_from(&[0x33, 0x00, 0x00, 0x04, 0xff, b'2'][..]),
+            Err(Error::UnexpectedResponse)
+        ));
+    }
+
+    #[cfg(all(feature = "transport_tcp", feature = "transport_usb"))]
+    #[test]
+    fn conn_info_try_from() {
+        let i = LedgerInfo {
+            model: Model::Unknown(0),
+            label: None,
+            conn: transport::TcpInfo::default().into(),
+        };
+
+        assert_eq!(i.conn.as_tcp(), Some(&transport::TcpInfo::default()));
+        assert_eq!(i.conn.as_usb(), None);
+
+        assert_eq!(
+            transport::TcpInfo::try_from(i.clone()).unwrap(),
+            transport::TcpInfo::default()
+        );
+        assert!(matches!(
+            transport::UsbInfo::try_from(i),
+            Err(Error::ConnTypeMismatch {
+                expected: ConnType::Usb,
+                found: ConnType::Tcp
+            })
+        ));
+    }
+
+    #[cfg(feature = "transport_tcp")]
+    #[test]
+    fn info_label() {
+        let i = LedgerInfo {
+            model: Model::NanoX,
+            conn: transport::TcpInfo::default().into(),
+            label: None,
+        };
+        assert_eq!(i.to_string(), "NanoX (TCP 127.0.0.1:1237)");
+        assert_eq!(i.stable_id().as_deref(), Some("127.0.0.1:1237"));
+
+        let l = i.clone().with_label("Savings");
+        assert_eq!(l.to_string(), "Savings (TCP 127.0.0.1:1237)");
+        assert_eq!(l, i);
+    }
+
+    #[test]
+    fn info_map_keys() {
+        use std::collections::HashMap;
+
+        let mut counts = HashMap::new();
+        for m in [Model::NanoX, Model::Stax, Model::NanoX, Model::Unknown(1)] {
+            *counts.entry(m).or_insert(0) += 1;
+        }
+        assert_eq!(counts[&Model::NanoX], 2);
+        assert_eq!(counts[&Model::Unknown(1)], 1);
+
+        let conns: HashMap<ConnType, &str> = [(ConnType::Tcp, "tcp")].into_iter().collect();
+        assert_eq!(conns.get(&ConnType::Tcp), Some(&"tcp"));
+    }
+}
@@ -54,13 +54,19 @@
 
 use std::time::Duration;
 
-use tracing::debug;
+use trace::debug;
+
+pub use tokio_util::sync::CancellationToken;
 
 use ledger_proto::{
     apdus::{ExitAppReq, RunAppReq},
     GenericApdu, StatusCode,
 };
 
+mod trace;
+
+mod runtime;
+
 pub mod info;
 pub use info::LedgerInfo;
 
@@ -70,21 +76,39 @@ pub use error::Error;
 pub mod transport;
 pub use transport::Transport;
 
+pub mod logging;
+
+pub mod apdu;
+use apdu::ApduCommand;
+
+pub mod diagnostics;
+
+pub mod transcript;
+
+pub mod metrics;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
 mod provider;
 pub use provider::{LedgerHandle, LedgerProvider};
 
 mod device;
 pub use device::Device;
 
+#[cfg(test)]
+mod mock;
+
 /// Default timeout helper for use with [Device] and [Exchange]
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
 
 /// Device discovery filter
-#[derive(Copy, Clone, Debug, PartialEq, strum::Display)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, strum::Display)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 #[non_exhaustive]
 pub enum Filters {
     /// List all devices available using supported transport
+    #[default]
     Any,
     /// List only HID devices
     Hid,
@@ -94,16 +118,111 @@ pub enum Filters {
     Ble,
 }
 
-impl Default for Filters {
-    fn default() -> Self {
-        Self::Any
-    }
-}
-
 /// [Exchange] trait provides a low-level interface for byte-wise exchange of APDU commands with a ledger devices
 #[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 pub trait Exchange {
     async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error>;
+
+    /// Exchange a sequence of APDUs with the device, stopping at the first
+    /// response without a success (`0x9000`) status word.
+    ///
+    /// Responses are returned in order, where a command fails this is the final response.
+    /// Transports may override this to reduce per-exchange setup costs.
+    async fn exchange_many(
+        &mut self,
+        commands: &[Vec<u8>],
+        timeout: Duration,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let mut responses = Vec::with_capacity(commands.len());
+
+        for c in commands {
+            let r = self.exchange(c, timeout).await?;
+
+            let ok = is_success(&r);
+            responses.push(r);
+            if !ok {
+                break;
+            }
+        }
+
+        Ok(responses)
+    }
+
+    /// Exchange an APDU with the device, returning the response data with the status word
+    /// removed on success (`0x9000`), or the status as an error otherwise.
+    ///
+    /// See [Exchange::exchange] for access to the raw response.
+    async fn exchange_checked(
+        &mut self,
+        command: &[u8],
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        let r = self.exchange(command, timeout).await?;
+        apdu::parse_response(&r)?.into_result()
+    }
+
+    /// Encode and exchange an [ApduCommand] with the device, returning the raw response
+    async fn exchange_apdu(
+        &mut self,
+        command: ApduCommand,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        let c = command.encode()?;
+        self.exchange(&c, timeout).await
+    }
+
+    /// Maximum payload bytes carried per transport-level frame
+    ///
+    /// This allows higher-level code to size chunks of large requests to avoid
+    /// redundant fragmentation, defaulting to the maximum short APDU data length.
+    fn max_payload_chunk(&self) -> usize {
+        u8::MAX as usize
+    }
+
+    /// Exchange an APDU with the device, returning [Error::Cancelled] if `cancel` is
+    /// triggered prior to a response being received.
+    ///
+    /// This is useful for long-running requests requiring user confirmation,
+    /// allowing applications to abort these from the host.
+    ///
+    /// Support depends on the transport: TCP devices reconnect on the following
+    /// exchange to discard the pending response, while USB exchanges are blocking
+    /// and may only be cancelled prior to the request being sent.
+    async fn exchange_cancellable(
+        &mut self,
+        command: &[u8],
+        timeout: Duration,
+        cancel: CancellationToken,
+    ) -> Result<Vec<u8>, Error> {
+        tokio::select! {
+            r = self.exchange(command, timeout) => r,
+            _ = cancel.cancelled() => Err(Error::Cancelled),
+        }
+    }
+}
+
+/// Helper to check a complete APDU response contains (at least) a status word,
+/// returning [Error::UnexpectedResponse] otherwise
+pub(crate) fn check_response(resp: &[u8]) -> Result<(), Error> {
+    if resp.len() < 2 {
+        trace::error!("Response missing status word ({} bytes)", resp.len());
+        return Err(Error::UnexpectedResponse);
+    }
+    Ok(())
+}
+
+/// Helper to convert a non-success status word to an [Error]
+pub(crate) fn status_error(sw1: u8, sw2: u8) -> Error {
+    match StatusCode::from_u16(u16::from_be_bytes([sw1, sw2])) {
+        Some(StatusCode::LockedDevice) => Error::DeviceLocked,
+        Some(c) => Error::Status(c),
+        None => Error::UnknownStatus(sw1, sw2),
+    }
+}
+
+/// Helper to check whether a response ends with a success (`0x9000`) status word
+pub(crate) fn is_success(resp: &[u8]) -> bool {
+    resp.ends_with(&[0x90, 0x00])
 }
 
 /// Blanket [Exchange] impl for mutable references
@@ -112,6 +231,35 @@ impl<T: Exchange + Send> Exchange for &mut T {
     async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
         <T as Exchange>::exchange(self, command, timeout).await
     }
+
+    async fn exchange_many(
+        &mut self,
+        commands: &[Vec<u8>],
+        timeout: Duration,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        <T as Exchange>::exchange_many(self, commands, timeout).await
+    }
+
+    async fn exchange_checked(
+        &mut self,
+        command: &[u8],
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        <T as Exchange>::exchange_checked(self, command, timeout).await
+    }
+
+    fn max_payload_chunk(&self) -> usize {
+        <T as Exchange>::max_payload_chunk(self)
+    }
+
+    async fn exchange_cancellable(
+        &mut self,
+        command: &[u8],
+        timeout: Duration,
+        cancel: CancellationToken,
+    ) -> Result<Vec<u8>, Error> {
+        <T as Exchange>::exchange_cancellable(self, command, timeout, cancel).await
+    }
 }
 
 /// Launch an application by name and return a device handle.
@@ -151,7 +299,7 @@ where
     }
 
     // Send an exit request to the running app
-    if i.name != "BOLOS" {
+    if !i.is_dashboard() {
         debug!("Exiting running app {}", i.name);
 
         match d
@@ -167,7 +315,7 @@ where
         // Close and re-connect to the device
         drop(d);
 
-        tokio::time::sleep(Duration::from_secs(opts.reconnect_delay_s as u64)).await;
+        runtime::sleep(Duration::from_secs(opts.reconnect_delay_s as u64)).await;
 
         d = reconnect(&mut t, info.clone(), opts).await?;
     }
@@ -189,14 +337,14 @@ where
                 // Re-connect to the device following app loading
                 drop(d);
 
-                tokio::time::sleep(Duration::from_secs(opts.reconnect_delay_s as u64)).await;
+                runtime::sleep(Duration::from_secs(opts.reconnect_delay_s as u64)).await;
 
                 d = reconnect(&mut t, info.clone(), opts).await?;
 
                 return Ok(d);
             }
             // Empty response, pending reply
-            Err(Error::EmptyResponse) => tokio::time::sleep(Duration::from_secs(1)).await,
+            Err(Error::EmptyResponse) => runtime::sleep(Duration::from_secs(1)).await,
             // Error response, something failed
             Err(e) => return Err(e),
         }
@@ -256,7 +404,7 @@ async fn reconnect<T: Transport<Info = LedgerInfo, Filters = Filters>>(
                 new_info = Some(i.clone());
                 break;
             }
-            None => tokio::time::sleep(Duration::from_secs(1)).await,
+            None => runtime::sleep(Duration::from_secs(1)).await,
         };
     }
 
@@ -273,3 +421,52 @@ async fn reconnect<T: Transport<Info = LedgerInfo, Filters = Filters>>(
     // Return new device connection
     Ok(d)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mock::ScriptedExchange;
+
+    #[tokio::test]
+    async fn exchange_many_short_circuits() {
+        let mut d =
+            ScriptedExchange::new(vec![vec![0x90, 0x00], vec![0x6a, 0x80], vec![0x90, 0x00]]);
+
+        let commands = vec![vec![0xe0, 0x01, 0x00, 0x00, 0x00]; 3];
+        let r = d.exchange_many(&commands, DEFAULT_TIMEOUT).await.unwrap();
+
+        assert_eq!(r, vec![vec![0x90, 0x00], vec![0x6a, 0x80]]);
+        assert_eq!(d.requests, 2);
+    }
+
+    #[tokio::test]
+    async fn exchange_checked() {
+        let mut d = ScriptedExchange::new(vec![
+            vec![0x01, 0x02, 0x90, 0x00],
+            vec![0x69, 0x85],
+            vec![0x12, 0x34],
+            vec![0x55, 0x15],
+            vec![0x90],
+        ]);
+
+        let c = [0xe0, 0x01, 0x00, 0x00, 0x00];
+        let r = d.exchange_checked(&c, DEFAULT_TIMEOUT).await;
+        assert_eq!(r.unwrap(), vec![0x01, 0x02]);
+
+        let r = d.exchange_checked(&c, DEFAULT_TIMEOUT).await;
+        assert!(matches!(
+            r,
+            Err(Error::Status(StatusCode::ConditionsOfUseNotSatisfied))
+        ));
+
+        let r = d.exchange_checked(&c, DEFAULT_TIMEOUT).await;
+        assert!(matches!(r, Err(Error::UnknownStatus(0x12, 0x34))));
+
+        let r = d.exchange_checked(&c, DEFAULT_TIMEOUT).await;
+        assert!(matches!(r, Err(Error::DeviceLocked)));
+
+        let r = d.exchange_checked(&c, DEFAULT_TIMEOUT).await;
+        assert!(matches!(r, Err(Error::UnexpectedResponse)));
+    }
+}
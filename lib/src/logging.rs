@@ -0,0 +1,147 @@
+//! APDU logging hooks for integrator diagnostics
+//!
+//! Loggers are invoked once per logical APDU (command or response), independent of
+//! transport-level framing, see `set_apdu_logger` on each device type.
+//!
+//! APDU payloads in `tracing` output are redacted by default in release builds,
+//! see [set_log_redaction] to override this.
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+/// Direction of a logged APDU
+#[derive(Copy, Clone, PartialEq, Debug, strum::Display)]
+pub enum Direction {
+    /// Command sent to the device
+    Tx,
+    /// Response received from the device
+    Rx,
+}
+
+/// APDU logging callback
+pub type ApduLogger = Arc<dyn Fn(Direction, &[u8]) + Send + Sync>;
+
+/// Helper to invoke an optional [ApduLogger]
+pub(crate) fn log_apdu(logger: &Option<ApduLogger>, dir: Direction, data: &[u8]) {
+    if let Some(l) = logger {
+        l(dir, data)
+    }
+}
+
+/// Default number of payload bytes included in trace output
+pub const DEFAULT_LOG_PAYLOAD_LIMIT: usize = 16;
+
+/// Global payload redaction flag, enabled by default for release builds
+static REDACT: AtomicBool = AtomicBool::new(!cfg!(debug_assertions));
+
+/// Enable or disable redaction of APDU payloads in log output
+///
+/// When enabled only the payload length is logged, avoiding leaking derivation
+/// paths or signing data into application logs.
+pub fn set_log_redaction(enabled: bool) {
+    REDACT.store(enabled, Ordering::Relaxed);
+}
+
+/// Check whether APDU payloads are redacted in log output
+pub fn log_redaction() -> bool {
+    REDACT.load(Ordering::Relaxed)
+}
+
+/// Counter for generating exchange identifiers
+#[cfg_attr(
+    not(all(feature = "transport_ble", feature = "tracing")),
+    allow(dead_code)
+)]
+static EXCHANGE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Fetch a unique identifier for correlating exchange spans
+#[cfg_attr(
+    not(all(feature = "transport_ble", feature = "tracing")),
+    allow(dead_code)
+)]
+pub(crate) fn next_exchange_id() -> u64 {
+    EXCHANGE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Payload wrapper for trace output, truncating data beyond `limit` bytes
+/// (or redacting all data, see [set_log_redaction]) as APDUs may carry
+/// sensitive information
+pub(crate) struct Payload<'a> {
+    data: &'a [u8],
+    limit: usize,
+    redact: bool,
+}
+
+impl<'a> Payload<'a> {
+    /// Create a payload truncated to `limit` bytes
+    pub fn new(data: &'a [u8], limit: usize) -> Self {
+        Self {
+            data,
+            limit,
+            redact: log_redaction(),
+        }
+    }
+
+    /// Create an untruncated payload (still subject to redaction)
+    #[cfg_attr(
+        not(any(feature = "transport_usb", feature = "transport_tcp")),
+        allow(dead_code)
+    )]
+    pub fn full(data: &'a [u8]) -> Self {
+        Self::new(data, usize::MAX)
+    }
+}
+
+impl std::fmt::Debug for Payload<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.redact {
+            return write!(f, "<{} bytes>", self.data.len());
+        }
+
+        if self.data.len() <= self.limit {
+            return write!(f, "{:02x?}", self.data);
+        }
+
+        write!(
+            f,
+            "{:02x?}.. (+{} bytes)",
+            &self.data[..self.limit],
+            self.data.len() - self.limit
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(data: &[u8], limit: usize, redact: bool) -> String {
+        format!(
+            "{:?}",
+            Payload {
+                data,
+                limit,
+                redact
+            }
+        )
+    }
+
+    #[test]
+    fn payload_truncation() {
+        let d = [0xe0, 0x01, 0x00, 0x00, 0x00];
+
+        assert_eq!(payload(&d, 16, false), "[e0, 01, 00, 00, 00]");
+        assert_eq!(payload(&d, 2, false), "[e0, 01].. (+3 bytes)");
+        assert_eq!(payload(&d, 0, false), "[].. (+5 bytes)");
+    }
+
+    #[test]
+    fn payload_redaction() {
+        let d = [0xe0, 0x01, 0x00, 0x00, 0x00];
+
+        assert_eq!(payload(&d, 16, true), "<5 bytes>");
+        assert_eq!(payload(&d, 2, true), "<5 bytes>");
+    }
+}
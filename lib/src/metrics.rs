@@ -0,0 +1,162 @@
+//! Exchange metrics collection
+//!
+//! Devices invoke a [Metrics] implementation (see `set_metrics` on each device type)
+//! at the start and end of each exchange, allowing integration with existing
+//! metrics exporters. [InMemoryMetrics] provides simple aggregate counters.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{info::ConnType, is_success, Error};
+
+/// Exchange metrics hook, all methods default to no-ops
+pub trait Metrics: Send + Sync {
+    /// Called prior to each exchange
+    fn exchange_start(&self, _conn: ConnType, _command: &[u8]) {}
+
+    /// Called on completion of each exchange with the elapsed time and outcome
+    fn exchange_end(
+        &self,
+        _conn: ConnType,
+        _duration: Duration,
+        _outcome: &Result<Vec<u8>, Error>,
+    ) {
+    }
+}
+
+/// Shared [Metrics] handle, as used by devices
+pub type MetricsHandle = Arc<dyn Metrics>;
+
+/// [Metrics] implementation discarding all events
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+/// [Metrics] implementation collecting aggregate counters
+#[derive(Debug, Default)]
+pub struct InMemoryMetrics {
+    exchanges: AtomicU64,
+    status_errors: AtomicU64,
+    timeouts: AtomicU64,
+    closed: AtomicU64,
+    other_errors: AtomicU64,
+    total_micros: AtomicU64,
+}
+
+/// Point-in-time snapshot of [InMemoryMetrics] counters
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MetricsSnapshot {
+    /// Completed exchanges (including failures)
+    pub exchanges: u64,
+    /// Responses with a non-success status word
+    pub status_errors: u64,
+    /// Exchanges failing with [Error::Timeout]
+    pub timeouts: u64,
+    /// Exchanges failing with [Error::Closed]
+    pub closed: u64,
+    /// Exchanges failing with any other error
+    pub other_errors: u64,
+    /// Average exchange latency
+    pub avg_latency: Duration,
+}
+
+impl InMemoryMetrics {
+    /// Create a new [InMemoryMetrics] instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch a snapshot of the current counters
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let exchanges = self.exchanges.load(Ordering::Relaxed);
+        let total = self.total_micros.load(Ordering::Relaxed);
+
+        MetricsSnapshot {
+            exchanges,
+            status_errors: self.status_errors.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            closed: self.closed.load(Ordering::Relaxed),
+            other_errors: self.other_errors.load(Ordering::Relaxed),
+            avg_latency: match exchanges {
+                0 => Duration::ZERO,
+                n => Duration::from_micros(total / n),
+            },
+        }
+    }
+}
+
+impl Metrics for InMemoryMetrics {
+    fn exchange_end(&self, _conn: ConnType, duration: Duration, outcome: &Result<Vec<u8>, Error>) {
+        self.exchanges.fetch_add(1, Ordering::Relaxed);
+        self.total_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+
+        let counter = match outcome {
+            Ok(r) if is_success(r) => return,
+            Ok(_) | Err(Error::Status(_) | Error::UnknownStatus(..) | Error::DeviceLocked) => {
+                &self.status_errors
+            }
+            Err(Error::Timeout) => &self.timeouts,
+            Err(Error::Closed) => &self.closed,
+            Err(_) => &self.other_errors,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Helper to invoke an optional [Metrics] handle around an exchange
+pub(crate) async fn with_metrics(
+    metrics: &Option<MetricsHandle>,
+    conn: ConnType,
+    command: &[u8],
+    f: impl Future<Output = Result<Vec<u8>, Error>>,
+) -> Result<Vec<u8>, Error> {
+    let m = match metrics {
+        Some(m) => m,
+        None => return f.await,
+    };
+
+    m.exchange_start(conn, command);
+    let start = Instant::now();
+
+    let r = f.await;
+
+    m.exchange_end(conn, start.elapsed(), &r);
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn in_memory_metrics() {
+        let m = Arc::new(InMemoryMetrics::new());
+        let h: Option<MetricsHandle> = Some(m.clone());
+
+        let outcomes = [
+            Ok(vec![0x90, 0x00]),
+            Ok(vec![0x69, 0x85]),
+            Err(Error::Timeout),
+            Err(Error::Closed),
+            Err(Error::Unknown),
+        ];
+        for o in outcomes {
+            let _ = with_metrics(&h, ConnType::Tcp, &[0xe0], async { o }).await;
+        }
+
+        let s = m.snapshot();
+        assert_eq!(s.exchanges, 5);
+        assert_eq!(s.status_errors, 1);
+        assert_eq!(s.timeouts, 1);
+        assert_eq!(s.closed, 1);
+        assert_eq!(s.other_errors, 1);
+    }
+}
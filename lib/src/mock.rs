@@ -0,0 +1,41 @@
+//! Mock [Exchange] implementations shared by unit tests
+
+use std::time::Duration;
+
+use crate::{Error, Exchange};
+
+/// Mock device echoing commands with a success status
+pub(crate) struct EchoExchange;
+
+#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+impl Exchange for EchoExchange {
+    async fn exchange(&mut self, command: &[u8], _timeout: Duration) -> Result<Vec<u8>, Error> {
+        Ok([command, &[0x90, 0x00]].concat())
+    }
+}
+
+/// Mock device returning scripted responses in order, ignoring commands
+pub(crate) struct ScriptedExchange {
+    responses: Vec<Vec<u8>>,
+    /// Number of requests received
+    pub requests: usize,
+}
+
+impl ScriptedExchange {
+    /// Create a mock device returning the provided responses
+    pub fn new(responses: Vec<Vec<u8>>) -> Self {
+        Self {
+            responses,
+            requests: 0,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+impl Exchange for ScriptedExchange {
+    async fn exchange(&mut self, _command: &[u8], _timeout: Duration) -> Result<Vec<u8>, Error> {
+        let r = self.responses[self.requests].clone();
+        self.requests += 1;
+        Ok(r)
+    }
+}
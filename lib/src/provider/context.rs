@@ -1,14 +1,15 @@
 use std::collections::HashMap;
 
+use crate::trace::{debug, error, warn};
 use tokio::{
     runtime::Builder,
     sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
     task::LocalSet,
 };
-use tracing::{debug, error, warn};
 
 use crate::{
     error::Error,
+    logging::log_redaction,
     provider::{LedgerReq, LedgerResp, ReqChannel},
     transport::{GenericDevice, GenericTransport, Transport},
     Exchange,
@@ -26,6 +27,28 @@ struct ProviderImpl {
     device_index: usize,
 }
 
+/// Summarise a request for redacted log output, omitting APDU data
+fn req_summary(req: &LedgerReq) -> String {
+    match req {
+        LedgerReq::List(filters) => format!("List({filters:?})"),
+        LedgerReq::Connect(info) => format!("Connect({info})"),
+        LedgerReq::Req(h, apdu, _) => format!("Req({h}, <{} bytes>)", apdu.len()),
+        LedgerReq::ReqMany(h, apdus, _) => format!("ReqMany({h}, <{} apdus>)", apdus.len()),
+        LedgerReq::Close(h) => format!("Close({h})"),
+    }
+}
+
+/// Summarise a response for redacted log output, omitting APDU data
+fn resp_summary(resp: &LedgerResp) -> String {
+    match resp {
+        LedgerResp::Devices(d) => format!("Devices(<{} devices>)", d.len()),
+        LedgerResp::Handle(h) => format!("Handle({h})"),
+        LedgerResp::Resp(apdu) => format!("Resp(<{} bytes>)", apdu.len()),
+        LedgerResp::Resps(apdus) => format!("Resps(<{} apdus>)", apdus.len()),
+        LedgerResp::Error(e) => format!("Error({e})"),
+    }
+}
+
 /// Static provider context, provides a global singleton for ledger device comms
 pub struct ProviderContext {
     /// Channel for sending requests to the provider task
@@ -107,10 +130,16 @@ impl ProviderImpl {
 
         // Poll on incoming requests
         while let Some((req, tx)) = self.req_rx.recv().await {
-            debug!("LedgerProvider request: {:02x?}", req);
+            match log_redaction() {
+                true => debug!("LedgerProvider request: {}", req_summary(&req)),
+                false => debug!("LedgerProvider request: {:02x?}", req),
+            }
 
             if let Some(resp) = self.handle_req(&req).await {
-                debug!("LedgerProvider response: {:02x?}", resp);
+                match log_redaction() {
+                    true => debug!("LedgerProvider response: {}", resp_summary(&resp)),
+                    false => debug!("LedgerProvider response: {:02x?}", resp),
+                }
 
                 if let Err(e) = tx.send(resp) {
                     error!("Failed to forward response: {}", e);
@@ -193,6 +222,25 @@ impl ProviderImpl {
                     Err(e) => LedgerResp::Error(e),
                 }
             }
+            LedgerReq::ReqMany(index, apdus, timeout) => {
+                // Fetch device handle
+                let d = match self.devices.get_mut(index) {
+                    Some(d) => d,
+                    None => {
+                        error!(
+                            "Attempted to send APDUs to unknown device handle: {}",
+                            index
+                        );
+                        return Some(LedgerResp::Error(Error::Unknown));
+                    }
+                };
+
+                // Issue APDU requests to device and return responses
+                match Exchange::exchange_many(d, apdus, *timeout).await {
+                    Ok(r) => LedgerResp::Resps(r),
+                    Err(e) => LedgerResp::Error(e),
+                }
+            }
             LedgerReq::Close(index) => {
                 // Drop device handle
                 if let Some(d) = self.devices.remove(index) {
@@ -207,3 +255,19 @@ impl ProviderImpl {
         Some(resp)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn redacted_summaries() {
+        let req = LedgerReq::Req(3, vec![0xe0, 0x04, 0x00, 0x00], Duration::from_secs(1));
+        assert_eq!(req_summary(&req), "Req(3, <4 bytes>)");
+
+        let resp = LedgerResp::Resp(vec![0x01, 0x02, 0x90, 0x00]);
+        assert_eq!(resp_summary(&resp), "Resp(<4 bytes>)");
+    }
+}
@@ -1,7 +1,7 @@
 //! [LedgerProvider] provides a tokio-based thread-safe interface for
 //! interacting with ledger devices.
 
-use std::time::Duration;
+use std::{future::Future, time::Duration};
 
 use tokio::sync::{
     mpsc::{unbounded_channel, UnboundedSender},
@@ -42,6 +42,9 @@ pub enum LedgerReq {
     /// APDU request issued to a device handle
     Req(usize, Vec<u8>, Duration),
 
+    /// Batch of APDU requests issued to a device handle
+    ReqMany(usize, Vec<Vec<u8>>, Duration),
+
     /// Close the device handle
     Close(usize),
 }
@@ -58,6 +61,9 @@ pub enum LedgerResp {
     /// APDU response from a device handle
     Resp(Vec<u8>),
 
+    /// APDU responses to a batch request from a device handle
+    Resps(Vec<Vec<u8>>),
+
     /// Error / operation failure
     Error(Error),
 }
@@ -81,6 +87,35 @@ impl LedgerProvider {
             req_tx: ctx.req_tx(),
         }
     }
+
+    /// Run an operation against each available device matching `filters`
+    ///
+    /// Devices are connected sequentially, with each [LedgerHandle] passed to `f`
+    /// and disconnected when this is dropped. Connection or operation failures are
+    /// returned per-device and do not stop iteration.
+    pub async fn for_each<F, R>(
+        &mut self,
+        filters: Filters,
+        mut f: F,
+    ) -> Result<Vec<(LedgerInfo, Result<(), Error>)>, Error>
+    where
+        F: FnMut(LedgerHandle) -> R,
+        R: Future<Output = Result<(), Error>>,
+    {
+        let devices = self.list(filters).await?;
+        let mut results = Vec::with_capacity(devices.len());
+
+        for info in devices {
+            let r = match self.connect(info.clone()).await {
+                Ok(d) => f(d).await,
+                Err(e) => Err(e),
+            };
+
+            results.push((info, r));
+        }
+
+        Ok(results)
+    }
 }
 
 /// [Transport] implementation for high-level [LedgerProvider]
@@ -147,6 +182,29 @@ impl Exchange for LedgerHandle {
             _ => Err(Error::Unknown),
         }
     }
+
+    async fn exchange_many(
+        &mut self,
+        commands: &[Vec<u8>],
+        timeout: Duration,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let (tx, mut rx) = unbounded_channel::<LedgerResp>();
+
+        // Send batch request
+        self.req_tx
+            .send((
+                LedgerReq::ReqMany(self.index, commands.to_vec(), timeout),
+                tx,
+            ))
+            .map_err(|_| Error::Unknown)?;
+
+        // Await APDU responses
+        match rx.recv().await {
+            Some(LedgerResp::Resps(data)) => Ok(data),
+            Some(LedgerResp::Error(e)) => Err(e),
+            _ => Err(Error::Unknown),
+        }
+    }
 }
 
 /// [Drop] impl sends close message to provider when [LedgerHandle] is dropped
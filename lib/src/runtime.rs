@@ -0,0 +1,57 @@
+//! Timer helpers used by transports
+//!
+//! Transport sleeps, timeouts, and intervals use these helpers, which forward to `tokio`
+//! and define the timeout to [Error::Timeout] mapping in one place.
+//!
+//! This is not a runtime abstraction: the crate requires the `tokio` runtime and
+//! alternate runtimes (eg. `async-std` or `smol`) are not supported. `btleplug` 0.10
+//! (used by the BLE transport) spawns tasks on `tokio`, and transports and the provider
+//! also use `tokio` tasks, sockets, channels, and `select!` directly.
+
+use std::{future::Future, time::Duration};
+
+use crate::Error;
+
+/// Sleep for the provided duration
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// Create an interval ticking every `period`, with the first tick completing immediately
+#[cfg_attr(not(feature = "transport_ble"), allow(dead_code))]
+pub(crate) fn interval(period: Duration) -> tokio::time::Interval {
+    tokio::time::interval(period)
+}
+
+/// Await a future with a timeout, returning [Error::Timeout] if this elapses
+pub(crate) async fn timeout<F: Future>(duration: Duration, f: F) -> Result<F::Output, Error> {
+    tokio::time::timeout(duration, f)
+        .await
+        .map_err(|_| Error::Timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn timeout_elapsed() {
+        let r = timeout(Duration::from_millis(10), sleep(Duration::from_secs(1))).await;
+        assert!(matches!(r, Err(Error::Timeout)));
+
+        let r = timeout(Duration::from_secs(1), async { 5 }).await;
+        assert!(matches!(r, Ok(5)));
+    }
+
+    #[tokio::test]
+    async fn interval_ticks() {
+        let mut t = interval(Duration::from_millis(50));
+
+        // First tick completes immediately, following ticks after each period
+        let start = std::time::Instant::now();
+        t.tick().await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+        t.tick().await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,52 @@
+//! Logging macro shim, forwarding to `tracing` when the `tracing` feature is enabled
+//! and compiling to no-ops otherwise.
+
+#[cfg(feature = "tracing")]
+#[allow(unused_imports)]
+pub(crate) use tracing::{debug, error, trace, warn};
+
+#[cfg(not(feature = "tracing"))]
+mod noop {
+    // Not all macros are used by every transport feature combination
+    #![allow(unused_macros, unused_imports)]
+
+    // Arguments are type-checked (so usage is identical to `tracing`) but never evaluated
+
+    macro_rules! log_debug {
+        ($($t:tt)*) => {{
+            if false {
+                let _ = ::std::format!($($t)*);
+            }
+        }};
+    }
+
+    macro_rules! log_error {
+        ($($t:tt)*) => {{
+            if false {
+                let _ = ::std::format!($($t)*);
+            }
+        }};
+    }
+
+    macro_rules! log_trace {
+        ($($t:tt)*) => {{
+            if false {
+                let _ = ::std::format!($($t)*);
+            }
+        }};
+    }
+
+    macro_rules! log_warn {
+        ($($t:tt)*) => {{
+            if false {
+                let _ = ::std::format!($($t)*);
+            }
+        }};
+    }
+
+    pub(crate) use {log_debug as debug, log_error as error, log_trace as trace, log_warn as warn};
+}
+
+#[cfg(not(feature = "tracing"))]
+#[allow(unused_imports)]
+pub(crate) use noop::{debug, error, trace, warn};
@@ -0,0 +1,147 @@
+//! APDU transcript recording and replay, for capturing sessions when debugging
+
+use std::time::{Duration, Instant};
+
+use crate::{Error, Exchange};
+
+#[cfg(any(test, feature = "test_util"))]
+use crate::trace::error;
+
+/// Recorded APDU exchange
+#[derive(Clone, PartialEq, Debug)]
+pub struct TranscriptEntry {
+    /// Command APDU
+    pub command: Vec<u8>,
+    /// Response APDU (including status word)
+    pub response: Vec<u8>,
+    /// Exchange duration
+    pub duration: Duration,
+}
+
+/// [Exchange] wrapper recording a transcript of each successful exchange,
+/// see [ReplayExchange] to serve this back
+///
+/// Failed exchanges are passed through without being recorded.
+pub struct RecordingExchange<T> {
+    inner: T,
+    transcript: Vec<TranscriptEntry>,
+}
+
+impl<T> RecordingExchange<T> {
+    /// Wrap an [Exchange] implementation to record exchanges
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            transcript: vec![],
+        }
+    }
+
+    /// Fetch the recorded transcript
+    pub fn transcript(&self) -> &[TranscriptEntry] {
+        &self.transcript
+    }
+
+    /// Return the inner [Exchange] and recorded transcript
+    pub fn into_parts(self) -> (T, Vec<TranscriptEntry>) {
+        (self.inner, self.transcript)
+    }
+}
+
+#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+impl<T: Exchange + Send> Exchange for RecordingExchange<T> {
+    async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        let start = Instant::now();
+
+        let response = self.inner.exchange(command, timeout).await?;
+
+        self.transcript.push(TranscriptEntry {
+            command: command.to_vec(),
+            response: response.clone(),
+            duration: start.elapsed(),
+        });
+
+        Ok(response)
+    }
+
+    fn max_payload_chunk(&self) -> usize {
+        self.inner.max_payload_chunk()
+    }
+}
+
+/// [Exchange] implementation serving a recorded transcript (see [RecordingExchange])
+///
+/// Commands must be issued in the recorded order, mismatched commands fail with
+/// [Error::UnexpectedResponse] and exchanges past the end of the transcript
+/// with [Error::Closed].
+#[cfg(any(test, feature = "test_util"))]
+pub struct ReplayExchange {
+    transcript: std::collections::VecDeque<TranscriptEntry>,
+}
+
+#[cfg(any(test, feature = "test_util"))]
+impl ReplayExchange {
+    /// Create a [ReplayExchange] from a recorded transcript
+    pub fn new(transcript: Vec<TranscriptEntry>) -> Self {
+        Self {
+            transcript: transcript.into(),
+        }
+    }
+
+    /// Fetch the number of exchanges remaining in the transcript
+    pub fn remaining(&self) -> usize {
+        self.transcript.len()
+    }
+}
+
+#[cfg(any(test, feature = "test_util"))]
+#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+impl Exchange for ReplayExchange {
+    async fn exchange(&mut self, command: &[u8], _timeout: Duration) -> Result<Vec<u8>, Error> {
+        let e = match self.transcript.pop_front() {
+            Some(e) => e,
+            None => {
+                error!("Replay transcript exhausted");
+                return Err(Error::Closed);
+            }
+        };
+
+        if e.command != command {
+            error!(
+                "Replay command mismatch (expected {:02x?}, got {:02x?})",
+                e.command, command
+            );
+            return Err(Error::UnexpectedResponse);
+        }
+
+        Ok(e.response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::EchoExchange, DEFAULT_TIMEOUT};
+
+    #[tokio::test]
+    async fn record_replay() {
+        let mut d = RecordingExchange::new(EchoExchange);
+        for c in [[0xe0, 0x01], [0xe0, 0x02]] {
+            d.exchange(&c, DEFAULT_TIMEOUT).await.unwrap();
+        }
+
+        let (_, transcript) = d.into_parts();
+        assert_eq!(transcript.len(), 2);
+        assert_eq!(transcript[1].response, vec![0xe0, 0x02, 0x90, 0x00]);
+
+        let mut r = ReplayExchange::new(transcript);
+        let resp = r.exchange(&[0xe0, 0x01], DEFAULT_TIMEOUT).await.unwrap();
+        assert_eq!(resp, vec![0xe0, 0x01, 0x90, 0x00]);
+
+        let resp = r.exchange(&[0xe0, 0x03], DEFAULT_TIMEOUT).await;
+        assert!(matches!(resp, Err(Error::UnexpectedResponse)));
+
+        let resp = r.exchange(&[0xe0, 0x01], DEFAULT_TIMEOUT).await;
+        assert!(matches!(resp, Err(Error::Closed)));
+        assert_eq!(r.remaining(), 0);
+    }
+}
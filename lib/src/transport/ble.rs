@@ -1,35 +1,295 @@
 //! Bluetooth Low Energy (BLE) transport
 
-use std::{fmt::Display, pin::Pin, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    time::Duration,
+};
 
 use btleplug::{
     api::{
-        BDAddr, Central as _, Characteristic, Manager as _, Peripheral, ScanFilter,
-        ValueNotification, WriteType,
+        BDAddr, Central as _, CentralEvent, CharPropFlags, Characteristic, Manager as _,
+        Peripheral, ScanFilter, ValueNotification, WriteType,
     },
-    platform::Manager,
+    platform::{Adapter, Manager, PeripheralId},
 };
-use futures::{stream::StreamExt, Stream};
-use tracing::{debug, error, trace, warn};
+use futures::{stream::StreamExt, FutureExt, Stream};
+use tokio::{
+    sync::{broadcast, Mutex},
+    task::JoinHandle,
+};
+#[cfg(feature = "tracing")]
+use tracing::{debug_span, Instrument};
+
+#[cfg(feature = "tracing")]
+use crate::logging::next_exchange_id;
+
+use crate::trace::{debug, error, trace, warn};
 use uuid::{uuid, Uuid};
 
-use super::{Exchange, Transport};
+use super::{
+    ble_framing::{
+        decode_continuation, decode_header, decode_response, encode_frames, BLE_CMD_APDU,
+        BLE_CMD_CONTINUATION, BLE_HEADER_LEN,
+    },
+    Exchange, Transport, DEFAULT_MAX_RESPONSE_LEN,
+};
 use crate::{
-    info::{ConnInfo, LedgerInfo, Model},
-    Error,
+    check_response,
+    diagnostics::{benchmark, BenchReport},
+    info::{ConnInfo, ConnType, LedgerInfo, Model},
+    is_success,
+    logging::{log_apdu, ApduLogger, Direction, Payload, DEFAULT_LOG_PAYLOAD_LIMIT},
+    metrics::{with_metrics, MetricsHandle},
+    runtime, CancellationToken, Error,
 };
 
 /// Transport for listing and connecting to BLE connected Ledger devices
+///
+/// Connected [BleDevice]s own their peripheral handle and notification stream
+/// (notifications are routed per-peripheral by `btleplug`), so [BleTransport::list]
+/// may be called to scan for further devices while existing devices remain
+/// connected and in use.
 pub struct BleTransport {
     manager: Manager,
     peripherals: Vec<(LedgerInfo, btleplug::platform::Peripheral)>,
+    /// Devices from the last [Transport::list] call, see [BleTransport::connect_index]
+    listed: Vec<LedgerInfo>,
+    /// Peripherals with a live [BleDevice] handle
+    connected: Arc<StdMutex<HashSet<PeripheralId>>>,
+    /// Known device specs, initialised from [BLE_SPECS]
+    specs: Vec<BleSpec>,
+    /// Listed or connected peripherals, used to resolve connection events
+    known: Arc<StdMutex<HashMap<PeripheralId, LedgerInfo>>>,
+    /// Connection event channel (see [BleTransport::subscribe_events])
+    events: broadcast::Sender<DeviceEvent>,
+    /// Connection events keyed by peripheral, as device addresses are not unique
+    /// on all platforms (see [BleTransport::subscribe_peripheral_events])
+    peripheral_events: broadcast::Sender<(PeripheralId, DeviceEvent)>,
+    /// Background tasks forwarding adapter events
+    event_tasks: Vec<JoinHandle<()>>,
+    /// Set following the first scan, see [BLE_POWER_ON_DELAY]
+    scanned: bool,
+    /// Number of adapters currently scanning, see [BleTransport::is_scanning]
+    scanning: AtomicUsize,
+}
+
+/// Delay prior to re-scanning where the first scan on a new [BleTransport] finds no devices
+///
+/// `btleplug` does not expose adapter power state, and scans started prior to the
+/// platform stack powering on (eg. Core Bluetooth on app launch) are ignored.
+const BLE_POWER_ON_DELAY: Duration = Duration::from_secs(1);
+
+/// BLE device connection events, see [BleTransport::subscribe_events]
+#[derive(Clone, PartialEq, Debug)]
+pub enum DeviceEvent {
+    /// Device connected
+    Connected(LedgerInfo),
+    /// Device disconnected
+    Disconnected(LedgerInfo),
+    /// Reconnection attempt scheduled after `delay`, see [KeepAlive](super::KeepAlive)
+    Reconnecting {
+        info: LedgerInfo,
+        attempt: u32,
+        delay: Duration,
+    },
+}
+
+/// Connection event channel capacity
+const BLE_EVENT_CAPACITY: usize = 16;
+
+/// Transport parameters negotiated on connection, see [BleDevice::capabilities]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BleCapabilities {
+    /// Negotiated MTU
+    pub mtu: u8,
+    /// Whether writes are sent without response
+    pub write_without_response: bool,
+    /// Device model
+    pub model: Model,
+}
+
+/// Connected [BleDevice] with negotiated [BleCapabilities],
+/// see [BleTransport::connect_detailed]
+pub struct BleConnection {
+    pub device: BleDevice,
+    pub capabilities: BleCapabilities,
+}
+
+/// Preferred BLE connection interval, see [BleDevice::request_connection_priority]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ConnectionPriority {
+    /// Platform default connection interval
+    #[default]
+    Balanced,
+    /// Short connection interval, reducing exchange latency at the cost of power
+    High,
+    /// Long connection interval, reducing power consumption
+    LowPower,
+}
+
+/// BLE scan filters, used when listing devices via [BleTransport]
+///
+/// Note that `btleplug` reports duplicate advertisements on all platforms,
+/// so peripheral properties (eg. RSSI) are updated throughout a scan.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct BleFilters {
+    /// Restrict scanning to peripherals advertising known Ledger service UUIDs
+    ///
+    /// This reduces scan noise and allows scanning while backgrounded on iOS / macOS,
+    /// however devices that only advertise a name will not be discovered.
+    pub filter_services: bool,
+
+    /// Maximum number of devices to return (or `None` for no limit)
+    pub max_results: Option<usize>,
+}
+
+impl BleFilters {
+    /// Build the platform [ScanFilter] for these filters
+    fn scan_filter(&self, specs: &[BleSpec]) -> ScanFilter {
+        let services = match self.filter_services {
+            true => specs.iter().map(|s| s.service_uuid).collect(),
+            false => vec![],
+        };
+
+        ScanFilter { services }
+    }
 }
 
 /// BLE specific device information
-#[derive(Clone, Debug, PartialEq)]
+///
+/// [Debug] output redacts all but the final two octets of the device address,
+/// see [BleInfo::full_debug] where the full address is required.
+///
+/// Equality and hashing consider only the device name and address, as advertisement
+/// state (see [BleInfo::adv_state]) is transient and the peripheral ID
+/// (see [BleInfo::peripheral_id]) is unavailable for stored information.
+#[derive(Clone)]
 pub struct BleInfo {
     name: String,
     addr: BDAddr,
+    adv: Option<BleAdvState>,
+    id: Option<String>,
+}
+
+/// Manufacturer specific advertisement data reported by a BLE device
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BleAdvState {
+    /// Bluetooth SIG company identifier
+    pub company_id: u16,
+    /// Raw manufacturer data
+    pub data: Vec<u8>,
+}
+
+impl PartialEq for BleInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.addr == other.addr
+    }
+}
+
+impl Eq for BleInfo {}
+
+impl std::hash::Hash for BleInfo {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.addr.hash(state);
+    }
+}
+
+impl BleInfo {
+    /// Create a [BleInfo] from a known device name and address
+    ///
+    /// This allows reconnecting to a previously discovered device using stored information.
+    pub fn new(name: String, addr: BDAddr) -> Self {
+        Self {
+            name,
+            addr,
+            adv: None,
+            id: None,
+        }
+    }
+
+    /// Helper to attach the platform peripheral ID for a discovered device
+    fn with_peripheral(mut self, id: &PeripheralId) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    /// Fetch the platform peripheral ID (see [BleDevice::id]) in string form,
+    /// where this device was discovered by a [BleTransport]
+    ///
+    /// Unlike the device address (zeroed on macOS / iOS) this is reported on
+    /// all platforms.
+    pub fn peripheral_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Fetch an identifier for this device that is stable across listings, using the
+    /// peripheral ID where available or the device address where this is reported
+    pub(crate) fn stable_id(&self) -> Option<String> {
+        match (&self.id, self.addr) {
+            (Some(id), _) => Some(id.clone()),
+            (None, a) if a == BDAddr::default() => None,
+            (None, a) => Some(a.to_string()),
+        }
+    }
+
+    /// Fetch manufacturer advertisement data, where reported while scanning
+    pub fn adv_state(&self) -> Option<&BleAdvState> {
+        self.adv.as_ref()
+    }
+
+    /// Fetch the device name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Fetch the device address
+    pub fn addr(&self) -> BDAddr {
+        self.addr
+    }
+}
+
+impl BleInfo {
+    /// Check whether two [BleInfo]s refer to the same device
+    ///
+    /// Devices are matched by peripheral ID or address where available, as names may
+    /// change on connection (see [BleTransport::connect]), falling back to the name
+    /// where neither is available (eg. stored info on macOS / iOS).
+    pub(crate) fn same_device(&self, other: &BleInfo) -> bool {
+        if let (Some(a), Some(b)) = (&self.id, &other.id) {
+            return a == b;
+        }
+
+        match self.addr == BDAddr::default() || other.addr == BDAddr::default() {
+            true => self == other,
+            false => self.addr == other.addr,
+        }
+    }
+
+    /// Format [BleInfo] including the full device address
+    pub fn full_debug(&self) -> String {
+        format!("BleInfo {{ name: {:?}, addr: {} }}", self.name, self.addr)
+    }
+}
+
+impl std::fmt::Debug for BleInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let a = self.addr.into_inner();
+
+        f.debug_struct("BleInfo")
+            .field("name", &self.name)
+            .field(
+                "addr",
+                &format_args!("**:**:**:**:{:02X}:{:02X}", a[4], a[5]),
+            )
+            .finish()
+    }
 }
 
 impl Display for BleInfo {
@@ -39,22 +299,59 @@ impl Display for BleInfo {
 }
 
 /// BLE connected ledger device
+///
+/// Response notifications are subscribed once on connection and retained for the
+/// lifetime of the device, avoiding two additional GATT writes (subscribe and unsubscribe)
+/// and their round-trip latency for every exchange.
 pub struct BleDevice {
     pub info: BleInfo,
+    model: Model,
     mtu: u8,
     p: btleplug::platform::Peripheral,
     c_write: Characteristic,
     c_read: Characteristic,
+    /// Write type supported by the write characteristic
+    write_type: WriteType,
+    /// Response notification subscription, established on connection
+    notifications: Subscription<Notifications>,
+    /// Held for the duration of an exchange, pauses keepalive pings
+    busy: Arc<Mutex<()>>,
+    /// Background keepalive task (see [BleDevice::set_keepalive])
+    keepalive: Option<JoinHandle<()>>,
+    /// Connected peripheral tracking, shared with [BleTransport]
+    connected: Arc<StdMutex<HashSet<PeripheralId>>>,
+    /// APDU logging callback (see [BleDevice::set_apdu_logger])
+    logger: Option<ApduLogger>,
+    /// Exchange metrics handle (see [BleDevice::set_metrics])
+    metrics: Option<MetricsHandle>,
+    /// Maximum payload bytes included in trace output
+    log_limit: usize,
+    /// Maximum accepted response length
+    max_response_len: usize,
+    /// Delay between written chunks (see [BleDevice::set_write_chunk_delay])
+    write_chunk_delay: Option<Duration>,
+    /// APDU and continuation frame tags (see [BleDevice::set_frame_tags])
+    tags: (u8, u8),
 }
 
 /// Bluetooth spec for ledger devices
 /// see: https://github.com/LedgerHQ/ledger-live/blob/develop/libs/ledgerjs/packages/devices/src/index.ts#L32
+///
+/// Additional specs (eg. for new models or custom firmware) may be added
+/// using [BleTransport::register_spec].
 #[derive(Clone, PartialEq, Debug)]
-struct BleSpec {
+pub struct BleSpec {
+    /// Device model
     pub model: Model,
+    /// Advertised name substring, used to match devices that do not advertise services
+    pub name: &'static str,
+    /// Advertised service UUID
     pub service_uuid: Uuid,
+    /// Notify (response) characteristic UUID
     pub notify_uuid: Uuid,
+    /// Write (request) characteristic UUID
     pub write_uuid: Uuid,
+    /// Write without response characteristic UUID
     pub write_cmd_uuid: Uuid,
 }
 
@@ -62,6 +359,7 @@ struct BleSpec {
 const BLE_SPECS: &[BleSpec] = &[
     BleSpec {
         model: Model::NanoX,
+        name: "Nano X",
         service_uuid: uuid!("13d63400-2c97-0004-0000-4c6564676572"),
         notify_uuid: uuid!("13d63400-2c97-0004-0001-4c6564676572"),
         write_uuid: uuid!("13d63400-2c97-0004-0002-4c6564676572"),
@@ -69,46 +367,181 @@ const BLE_SPECS: &[BleSpec] = &[
     },
     BleSpec {
         model: Model::Stax,
+        name: "Stax",
         service_uuid: uuid!("13d63400-2c97-6004-0000-4c6564676572"),
         notify_uuid: uuid!("13d63400-2c97-6004-0001-4c6564676572"),
         write_uuid: uuid!("13d63400-2c97-6004-0002-4c6564676572"),
         write_cmd_uuid: uuid!("13d63400-2c97-6004-0003-4c6564676572"),
     },
+    BleSpec {
+        model: Model::Flex,
+        name: "Flex",
+        service_uuid: uuid!("13d63400-2c97-3004-0000-4c6564676572"),
+        notify_uuid: uuid!("13d63400-2c97-3004-0001-4c6564676572"),
+        write_uuid: uuid!("13d63400-2c97-3004-0002-4c6564676572"),
+        write_cmd_uuid: uuid!("13d63400-2c97-3004-0003-4c6564676572"),
+    },
 ];
 
 impl BleTransport {
+    /// Create a new [BleTransport], setting up an internal BLE [Manager]
     pub async fn new() -> Result<Self, Error> {
         // Setup connection manager
         let manager = Manager::new().await?;
 
-        Ok(Self {
+        Ok(Self::with_manager(manager))
+    }
+
+    /// Create a new [BleTransport] using an existing BLE [Manager]
+    ///
+    /// This allows applications that already own a [Manager] (for example
+    /// shared with other BLE features) to avoid creating a second instance.
+    pub fn with_manager(manager: Manager) -> Self {
+        Self {
             manager,
             peripherals: vec![],
-        })
+            listed: vec![],
+            connected: Arc::new(StdMutex::new(HashSet::new())),
+            specs: BLE_SPECS.to_vec(),
+            known: Arc::new(StdMutex::new(HashMap::new())),
+            events: broadcast::channel(BLE_EVENT_CAPACITY).0,
+            peripheral_events: broadcast::channel(BLE_EVENT_CAPACITY).0,
+            event_tasks: vec![],
+            scanned: false,
+            scanning: AtomicUsize::new(0),
+        }
+    }
+
+    /// Subscribe to connection events for listed or connected devices
+    ///
+    /// This allows applications to reflect live connection state without polling.
+    /// Adapter events are forwarded from the first call, and events for peripherals
+    /// not yet discovered via [Transport::list] or connected are ignored.
+    pub async fn subscribe_events(&mut self) -> Result<broadcast::Receiver<DeviceEvent>, Error> {
+        self.forward_events().await?;
+
+        Ok(self.events.subscribe())
+    }
+
+    /// Subscribe to connection events along with the [PeripheralId] of each device,
+    /// used to match events where device addresses are unavailable (eg. on macOS / iOS)
+    pub(crate) async fn subscribe_peripheral_events(
+        &mut self,
+    ) -> Result<broadcast::Receiver<(PeripheralId, DeviceEvent)>, Error> {
+        self.forward_events().await?;
+
+        Ok(self.peripheral_events.subscribe())
+    }
+
+    /// Helper to start forwarding adapter events, if not already running
+    async fn forward_events(&mut self) -> Result<(), Error> {
+        if !self.event_tasks.is_empty() {
+            return Ok(());
+        }
+
+        for adapter in self.adapters().await? {
+            let mut s = adapter.events().await?;
+            let (tx, id_tx, known) = (
+                self.events.clone(),
+                self.peripheral_events.clone(),
+                self.known.clone(),
+            );
+
+            self.event_tasks.push(tokio::task::spawn(async move {
+                while let Some(e) = s.next().await {
+                    let ev = map_event(e, &known.lock().unwrap());
+                    if let Some((id, ev)) = ev {
+                        debug!("Device event: {ev:?}");
+                        let _ = tx.send(ev.clone());
+                        let _ = id_tx.send((id, ev));
+                    }
+                }
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Fetch a stream of raw [CentralEvent]s from all available adapters
+    ///
+    /// Each call creates an independent subscription, so this does not interfere
+    /// with events consumed internally (eg. by [BleTransport::subscribe_events]).
+    ///
+    /// # Stability
+    /// This is gated behind the `unstable_ble_events` feature and exposes `btleplug`
+    /// types directly, so it may change with `btleplug` releases. Event semantics are
+    /// platform specific (the set and frequency of events, and [PeripheralId] formats
+    /// differ between CoreBluetooth, BlueZ and WinRT), and are primarily tested against
+    /// CoreBluetooth.
+    #[cfg(feature = "unstable_ble_events")]
+    pub async fn events(&mut self) -> Result<impl Stream<Item = CentralEvent>, Error> {
+        let mut streams = vec![];
+        for adapter in self.adapters().await? {
+            streams.push(adapter.events().await?);
+        }
+
+        Ok(futures::stream::select_all(streams))
+    }
+
+    /// Register an additional [BleSpec] for use in scanning and connection
+    ///
+    /// Registered specs take precedence over built-in specs for the same model.
+    pub fn register_spec(&mut self, spec: BleSpec) {
+        self.specs.insert(0, spec);
+    }
+
+    /// Helper to fetch available BLE adapters, returning [Error::BluetoothUnavailable]
+    /// where none are present
+    async fn adapters(&self) -> Result<Vec<Adapter>, Error> {
+        let adapters = self.manager.adapters().await?;
+
+        if adapters.is_empty() {
+            warn!("No bluetooth adapters available");
+            return Err(Error::BluetoothUnavailable);
+        }
+
+        Ok(adapters)
     }
 
     /// Helper to perform scan for available BLE devices, used in [list] and [connect].
+    ///
+    /// Scanning is stopped following the scan window, or early if `cancel` is triggered,
+    /// returning devices discovered so far.
     async fn scan_internal(
         &self,
+        filters: &BleFilters,
         duration: Duration,
+        cancel: &CancellationToken,
     ) -> Result<Vec<(LedgerInfo, btleplug::platform::Peripheral)>, Error> {
         let mut matched = vec![];
 
         // Grab adapter list
-        let adapters = self.manager.adapters().await?;
+        let adapters = self.adapters().await?;
 
-        // TODO: load filters?
-        let f = ScanFilter { services: vec![] };
+        let f = filters.scan_filter(&self.specs);
 
         // Search using adapters
         for adapter in adapters.iter() {
+            if cancel.is_cancelled() {
+                debug!("Scan cancelled");
+                break;
+            }
+
             let info = adapter.adapter_info().await?;
             debug!("Scan with adapter {info}");
 
             // Start scan with adaptor
-            adapter.start_scan(f.clone()).await?;
+            // (this fails where the adapter is powered off or otherwise unavailable)
+            self.start_scan(adapter, f.clone()).await?;
 
-            tokio::time::sleep(duration).await;
+            // Await scan window, stopping early on cancellation
+            tokio::select! {
+                _ = runtime::sleep(duration) => (),
+                _ = cancel.cancelled() => (),
+            }
+
+            // Stop scanning so the radio is not left active
+            self.stop_scan(adapter).await;
 
             // Fetch peripheral list
             let mut peripherals = adapter.peripherals().await?;
@@ -119,111 +552,432 @@ impl BleTransport {
 
             // Load peripheral information
             for p in peripherals.drain(..) {
-                // Fetch peripheral properties
-                let (properties, _connected) = (p.properties().await?, p.is_connected().await?);
-
-                // Skip peripherals where we couldn't fetch properties
-                let properties = match properties {
-                    Some(v) => v,
-                    None => {
-                        debug!("Failed to fetch properties for peripheral: {p:?}");
-                        continue;
-                    }
-                };
+                if let Some(i) = self.match_peripheral(&p).await? {
+                    matched.push((i, p));
+                }
+            }
+        }
 
-                // Skip peripherals without a local name (NanoX should report this)
-                let name = match &properties.local_name {
-                    Some(v) => v,
-                    None => continue,
-                };
+        // Remove duplicates (eg. where a peripheral is visible to multiple adapters)
+        let mut matched = dedupe_peripherals(matched, |(_, p)| p.id());
+
+        if let Some(n) = filters.max_results {
+            matched.truncate(n);
+        }
+
+        Ok(matched)
+    }
+
+    /// Helper to match a discovered peripheral against known device specs,
+    /// returning `None` for peripherals that are not ledger devices
+    async fn match_peripheral(
+        &self,
+        p: &btleplug::platform::Peripheral,
+    ) -> Result<Option<LedgerInfo>, Error> {
+        // Fetch peripheral properties, skipping peripherals where these are unavailable
+        let properties = match p.properties().await? {
+            Some(v) => v,
+            None => {
+                debug!("Failed to fetch properties for peripheral: {p:?}");
+                return Ok(None);
+            }
+        };
+
+        // Match model using advertised services, falling back to the local name
+        let model = match match_model(
+            &self.specs,
+            &properties.services,
+            properties.local_name.as_deref(),
+        ) {
+            Some(v) => v,
+            None => {
+                debug!(
+                    "Skipping peripheral {:?} ({}): no matching service or name",
+                    properties.local_name, properties.address
+                );
+                return Ok(None);
+            }
+        };
+
+        debug!("Peripheral: {p:?} props: {properties:?}");
+
+        // Retain manufacturer data (using the lowest company ID where multiple are reported)
+        let adv = properties
+            .manufacturer_data
+            .iter()
+            .min_by_key(|(id, _)| **id)
+            .map(|(id, d)| BleAdvState {
+                company_id: *id,
+                data: d.clone(),
+            });
+
+        // Use the address where no name is advertised
+        // (this is updated from the GAP device name on connection)
+        let name = properties
+            .local_name
+            .unwrap_or_else(|| properties.address.to_string());
+
+        Ok(Some(LedgerInfo {
+            model,
+            label: None,
+            conn: BleInfo {
+                name,
+                addr: properties.address,
+                adv,
+                id: Some(p.id().to_string()),
+            }
+            .into(),
+        }))
+    }
+
+    /// Helper to wait for the first matching device to be discovered, using adapter events
+    ///
+    /// Scanning is stopped on return, including where `timeout` elapses.
+    async fn wait_internal(
+        &self,
+        filters: &BleFilters,
+        timeout: Duration,
+    ) -> Result<(LedgerInfo, btleplug::platform::Peripheral), Error> {
+        let adapters = self.adapters().await?;
+
+        let mut started = vec![];
+        let r = runtime::timeout(
+            timeout,
+            self.discover_first(&adapters, filters, &mut started),
+        )
+        .await;
+
+        for i in started {
+            self.stop_scan(&adapters[i]).await;
+        }
+
+        r?
+    }
+
+    /// Helper to scan with all adapters until the first matching device is discovered,
+    /// recording the adapters where scanning was started in `started`
+    async fn discover_first(
+        &self,
+        adapters: &[Adapter],
+        filters: &BleFilters,
+        started: &mut Vec<usize>,
+    ) -> Result<(LedgerInfo, btleplug::platform::Peripheral), Error> {
+        let f = filters.scan_filter(&self.specs);
+
+        let mut streams = vec![];
+        for (i, adapter) in adapters.iter().enumerate() {
+            // Subscribe prior to scanning so no discovery events are missed
+            let events = adapter.events().await?;
+            streams.push(events.map(move |e| (i, e)).boxed());
+
+            self.start_scan(adapter, f.clone()).await?;
+            started.push(i);
+
+            // Check peripherals already known to the adapter
+            for p in adapter.peripherals().await? {
+                if let Some(i) = self.match_peripheral(&p).await? {
+                    return Ok((i, p));
+                }
+            }
+        }
 
-                debug!("Peripheral: {p:?} props: {properties:?}");
+        // Await discovery events
+        let mut events = futures::stream::select_all(streams);
+        while let Some((i, e)) = events.next().await {
+            let id = match e {
+                CentralEvent::DeviceDiscovered(id) | CentralEvent::DeviceUpdated(id) => id,
+                _ => continue,
+            };
 
-                // Match on peripheral names
-                let model = if name.contains("Nano X") {
-                    Model::NanoX
-                } else if name.contains("Stax") {
-                    Model::Stax
-                } else {
+            let p = match adapters[i].peripheral(&id).await {
+                Ok(p) => p,
+                Err(e) => {
+                    debug!("Failed to fetch peripheral {id:?}: {e:?}");
                     continue;
-                };
+                }
+            };
 
-                // Add to device list
-                matched.push((
-                    LedgerInfo {
-                        model: model.clone(),
-                        conn: BleInfo {
-                            name: name.clone(),
-                            addr: properties.address,
-                        }
-                        .into(),
-                    },
-                    p,
-                ));
+            if let Some(i) = self.match_peripheral(&p).await? {
+                return Ok((i, p));
             }
         }
 
-        Ok(matched)
+        Err(Error::NoDevices)
     }
-}
 
-/// [Transport] implementation for [BleTransport]
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
-impl Transport for BleTransport {
-    type Filters = ();
-    type Info = BleInfo;
-    type Device = BleDevice;
+    /// Helper to start scanning with an adapter, tracking active scans
+    /// (see [BleTransport::is_scanning])
+    async fn start_scan(&self, adapter: &Adapter, filter: ScanFilter) -> Result<(), Error> {
+        match adapter.start_scan(filter).await {
+            Ok(_) => {
+                self.scanning.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(btleplug::Error::PermissionDenied) => {
+                warn!("Bluetooth permission denied");
+                Err(Error::BluetoothUnauthorized)
+            }
+            Err(e) if is_adapter_unavailable(&e) => {
+                warn!("Bluetooth adapter unavailable: {e:?}");
+                Err(Error::BluetoothUnavailable)
+            }
+            Err(e) => {
+                warn!("Failed to start scan: {e:?}");
+                Err(Error::Ble(e))
+            }
+        }
+    }
 
-    /// List BLE connected ledger devices
-    async fn list(&mut self, _filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
+    /// Helper to stop scanning with an adapter previously started via [Self::start_scan]
+    async fn stop_scan(&self, adapter: &Adapter) {
+        if let Err(e) = adapter.stop_scan().await {
+            warn!("Failed to stop scan: {e:?}");
+        }
+        self.scanning.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Check whether a scan is in progress
+    ///
+    /// Scans are stopped when [Transport::list] or [Transport::wait_for_device] complete,
+    /// though may remain active where these futures are dropped prior to completion
+    /// (see [BleTransport::list_cancellable] to stop a scan early).
+    pub fn is_scanning(&self) -> bool {
+        self.scanning.load(Ordering::Relaxed) > 0
+    }
+
+    /// List BLE connected ledger devices, stopping the scan early if `cancel` is
+    /// triggered (eg. when a device picker is closed) and returning devices discovered so far
+    pub async fn list_cancellable(
+        &mut self,
+        filters: BleFilters,
+        cancel: CancellationToken,
+    ) -> Result<Vec<LedgerInfo>, Error> {
         // Scan for available devices
-        let devices = self.scan_internal(Duration::from_millis(1000)).await?;
+        let mut devices = self
+            .scan_internal(&filters, Duration::from_millis(1000), &cancel)
+            .await?;
+
+        // Retry the first scan, allowing the platform stack to power on
+        if devices.is_empty() && !self.scanned && !cancel.is_cancelled() {
+            debug!("No devices found on first scan, retrying after power on delay");
+            tokio::select! {
+                _ = runtime::sleep(BLE_POWER_ON_DELAY) => (),
+                _ = cancel.cancelled() => (),
+            }
+            devices = self
+                .scan_internal(&filters, Duration::from_millis(1000), &cancel)
+                .await?;
+        }
+        self.scanned = true;
 
         // Filter to return info list
         let info: Vec<_> = devices.iter().map(|d| d.0.clone()).collect();
 
-        // Save listed devices for next connect
-        self.peripherals = devices;
+        // Record peripherals for connection events
+        let mut known = self.known.lock().unwrap();
+        for (i, p) in devices.iter() {
+            known.insert(p.id(), i.clone());
+        }
+        drop(known);
+
+        // Save listed devices for next connect, retaining connected peripherals
+        // so existing device handles are not disrupted by a fresh scan
+        let connected = self.connected.lock().unwrap().clone();
+        self.peripherals = merge_peripherals(
+            std::mem::take(&mut self.peripherals),
+            devices,
+            |(_, p)| p.id(),
+            |id| connected.contains(id),
+        );
+
+        self.listed = info.clone();
 
         Ok(info)
     }
 
-    /// Connect to a specific ledger device
+    /// Connect to a specific ledger device (see [Transport::connect]), returning the
+    /// device with the transport parameters negotiated on connection
+    pub async fn connect_detailed(&mut self, info: BleInfo) -> Result<BleConnection, Error> {
+        let device = self.connect(info).await?;
+        let capabilities = device.capabilities();
+
+        Ok(BleConnection {
+            device,
+            capabilities,
+        })
+    }
+
+    /// Connect to a device by index into the results of the last [Transport::list] call,
+    /// returning [Error::InvalidDeviceIndex] where this is out of range
+    pub async fn connect_index(&mut self, idx: usize) -> Result<BleDevice, Error> {
+        let info = self.listed.get(idx).ok_or(Error::InvalidDeviceIndex(idx))?;
+        let info = BleInfo::try_from(info.clone())?;
+        self.connect(info).await
+    }
+
+    /// Connect to a device by address, bypassing the need for a prior [Transport::list]
     ///
-    /// Note: this _must_ follow a [Self::list] operation to match `info` with known peripherals
-    async fn connect(&mut self, info: Self::Info) -> Result<Self::Device, Error> {
-        // Match known peripherals using provided device info
-        let (d, p) = match self
+    /// This matches `addr` against peripherals already known to the platform adapters
+    /// (eg. previously discovered or paired devices), returning [Error::NoDevices] if the
+    /// peripheral is not available.
+    pub async fn connect_by_addr(
+        &mut self,
+        addr: BDAddr,
+        model: Model,
+    ) -> Result<BleDevice, Error> {
+        // Search adapters for a matching peripheral
+        for adapter in self.adapters().await? {
+            for p in adapter.peripherals().await? {
+                let properties = match p.properties().await? {
+                    Some(v) if v.address == addr => v,
+                    _ => continue,
+                };
+
+                let name = properties.local_name.unwrap_or_else(|| addr.to_string());
+
+                return self
+                    .connect_peripheral(
+                        BleInfo::new(name, addr).with_peripheral(&p.id()),
+                        model,
+                        p,
+                        &mut "connect",
+                    )
+                    .await;
+            }
+        }
+
+        warn!("No peripheral found with address: {addr}");
+        Err(Error::NoDevices)
+    }
+
+    /// Connect to a device by platform peripheral ID (see [BleDevice::id]),
+    /// bypassing the need for a prior [Transport::list]
+    ///
+    /// This allows applications to persist the identifier of a previously connected
+    /// device and reconnect directly, returning [Error::NoDevices] if the peripheral
+    /// is not known to any adapter.
+    pub async fn connect_by_id(&mut self, id: PeripheralId) -> Result<BleDevice, Error> {
+        for adapter in self.adapters().await? {
+            // Adapters return an error where the peripheral is unknown
+            let p = match adapter.peripheral(&id).await {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+
+            let properties = match p.properties().await? {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let model = match match_model(
+                &self.specs,
+                &properties.services,
+                properties.local_name.as_deref(),
+            ) {
+                Some(v) => v,
+                None => {
+                    warn!("Peripheral {id:?} is not a known ledger device");
+                    return Err(Error::NoDevices);
+                }
+            };
+
+            let addr = properties.address;
+            let name = properties.local_name.unwrap_or_else(|| addr.to_string());
+
+            return self
+                .connect_peripheral(
+                    BleInfo::new(name, addr).with_peripheral(&id),
+                    model,
+                    p,
+                    &mut "connect",
+                )
+                .await;
+        }
+
+        warn!("No peripheral found with id: {id:?}");
+        Err(Error::NoDevices)
+    }
+
+    /// Helper to match listed peripherals using the provided device info
+    fn find_peripheral(
+        &self,
+        info: &BleInfo,
+    ) -> Result<(Model, btleplug::platform::Peripheral), Error> {
+        match self
             .peripherals
             .iter()
-            .find(|(d, _p)| d.conn == info.clone().into())
+            .find(|(d, _p)| d.conn.as_ble().is_some_and(|d| d.same_device(info)))
         {
-            Some(v) => v,
+            Some((d, p)) => Ok((d.model.clone(), p.clone())),
             None => {
                 warn!("No device found matching: {info:?}");
-                return Err(Error::NoDevices);
+                Err(Error::NoDevices)
             }
-        };
-        let i = match &d.conn {
-            ConnInfo::Ble(i) => i,
-            _ => unreachable!(),
-        };
+        }
+    }
+
+    /// Helper to update recorded info for a peripheral (eg. following a GAP name read),
+    /// so later lookups and connection events match the info returned by the device
+    fn update_info(&mut self, id: &PeripheralId, prev: &BleInfo, info: &BleInfo) {
+        let (prev, conn): (ConnInfo, ConnInfo) = (prev.clone().into(), info.clone().into());
 
-        let name = &i.name;
+        if let Some(i) = self.known.lock().unwrap().get_mut(id) {
+            i.conn = conn.clone();
+        }
+
+        for (i, p) in self.peripherals.iter_mut() {
+            if &p.id() == id {
+                i.conn = conn.clone();
+            }
+        }
+
+        for i in self.listed.iter_mut().filter(|i| i.conn == prev) {
+            i.conn = conn.clone();
+        }
+    }
+
+    /// Helper to connect to a peripheral and setup a [BleDevice] handle
+    ///
+    /// `phase` is updated as connection progresses for timeout reporting.
+    async fn connect_peripheral(
+        &mut self,
+        mut info: BleInfo,
+        model: Model,
+        p: btleplug::platform::Peripheral,
+        phase: &mut &'static str,
+    ) -> Result<BleDevice, Error> {
+        let name = info.name.clone();
+
+        // Check we don't already have a live handle for this peripheral
+        if self.connected.lock().unwrap().contains(&p.id()) {
+            warn!("Peripheral {name} already connected");
+            return Err(Error::AlreadyConnected);
+        }
 
         // Fetch properties
         let properties = p.properties().await?;
 
         // Connect to device and subscribe to characteristics
         // Fetch specs for matched model (contains characteristic identifiers)
-        let specs = match BLE_SPECS.iter().find(|s| s.model == d.model) {
-            Some(v) => v,
+        // (models without BLE support, eg. the Nano S, have no specs)
+        let specs = match self.specs.iter().find(|s| s.model == model) {
+            Some(v) => v.clone(),
             None => {
-                warn!("No specs for model: {:?}", d.model);
-                return Err(Error::Unknown);
+                warn!("No specs for model: {:?}", model);
+                return Err(Error::Unsupported(model));
             }
         };
 
+        // Record peripheral for connection events
+        self.known.lock().unwrap().insert(
+            p.id(),
+            LedgerInfo {
+                model: model.clone(),
+                label: None,
+                conn: info.clone().into(),
+            },
+        );
+
         // If we're not connected, attempt to connect
         if !p.is_connected().await? {
             if let Err(e) = p.connect().await {
@@ -240,33 +994,83 @@ impl Transport for BleTransport {
         debug!("peripheral {name}: {p:?} properties: {properties:?}");
 
         // Then, grab available services and locate characteristics
-        p.discover_services().await?;
-
+        //
+        // On first connection the OS may be bonding with the device, during which
+        // discovery can transiently fail, so we retry once after a delay to allow
+        // bonding to complete.
+        *phase = "discovery";
+        let (c_write, c_read) = match discover_characteristics(&p, &specs).await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Discovery failed for {name} ({e:?}), retrying in case of pairing");
+                runtime::sleep(BLE_PAIRING_RETRY_DELAY).await;
+
+                match discover_characteristics(&p, &specs).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("Failed to match read and write characteristics for {name}");
+                        return Err(e);
+                    }
+                }
+            }
+        };
         let characteristics = p.characteristics();
 
-        trace!("Characteristics: {characteristics:?}");
-
-        let c_write = characteristics.iter().find(|c| c.uuid == specs.write_uuid);
-        let c_read = characteristics.iter().find(|c| c.uuid == specs.notify_uuid);
-
-        let (c_write, c_read) = match (c_write, c_read) {
-            (Some(w), Some(r)) => (w, r),
-            _ => {
-                error!("Failed to match read and write characteristics for {name}");
-                return Err(Error::Unknown);
+        // Where no name was advertised, attempt to read the GAP device name
+        let advertised = properties.as_ref().and_then(|p| p.local_name.as_ref());
+        if advertised.is_none() {
+            match characteristics
+                .iter()
+                .find(|c| c.uuid == GAP_DEVICE_NAME_UUID)
+            {
+                Some(c) => match p.read(c).await.map(String::from_utf8) {
+                    Ok(Ok(n)) if !n.is_empty() => {
+                        debug!("Using GAP device name: {n}");
+                        let prev = info.clone();
+                        info.name = n;
+                        self.update_info(&p.id(), &prev, &info);
+                    }
+                    r => debug!("Failed to read GAP device name: {r:?}"),
+                },
+                None => debug!("GAP device name characteristic not available"),
             }
-        };
+        }
+
+        // Select write type supported by the write characteristic
+        let write_type = select_write_type(c_write.properties);
+        debug!(
+            "Using write type {write_type:?} for {name} (properties: {:?})",
+            c_write.properties
+        );
 
         // Create device instance
         let mut d = BleDevice {
-            info: info.clone(),
-            mtu: 23,
+            info,
+            model: model.clone(),
+            mtu: BLE_DEFAULT_MTU,
             p: p.clone(),
-            c_write: c_write.clone(),
-            c_read: c_read.clone(),
+            c_write,
+            c_read,
+            write_type,
+            notifications: Subscription::default(),
+            busy: Arc::new(Mutex::new(())),
+            keepalive: None,
+            connected: self.connected.clone(),
+            logger: None,
+            metrics: None,
+            log_limit: DEFAULT_LOG_PAYLOAD_LIMIT,
+            max_response_len: DEFAULT_MAX_RESPONSE_LEN,
+            write_chunk_delay: None,
+            tags: (BLE_CMD_APDU, BLE_CMD_CONTINUATION),
         };
 
+        // Subscribe to responses for the lifetime of the device
+        // (avoiding subscribe / unsubscribe round-trips for each exchange)
+        *phase = "subscribe";
+        d.subscribe().await?;
+
         // Request MTU (cmd 0x08, seq: 0x0000, len: 0x0000)
+        *phase = "MTU request";
         match d.fetch_mtu().await {
             Ok(mtu) => d.mtu = mtu,
             Err(e) => {
@@ -276,171 +1080,1245 @@ impl Transport for BleTransport {
 
         debug!("using MTU: {}", d.mtu);
 
+        // Track connected peripheral until the device handle is dropped
+        self.connected.lock().unwrap().insert(p.id());
+
         Ok(d)
     }
 }
 
-const BLE_HEADER_LEN: usize = 3;
-
-impl BleDevice {
-    /// Helper to write commands as chunks based on device MTU
-    async fn write_command(&mut self, cmd: u8, payload: &[u8]) -> Result<(), Error> {
-        // Setup outgoing data (adds 2-byte big endian length prefix)
-        let mut data = Vec::with_capacity(payload.len() + 2);
-        data.extend_from_slice(&(payload.len() as u16).to_be_bytes()); // Data length
-        data.extend_from_slice(payload); // Data
-
-        debug!("TX cmd: 0x{cmd:02x} payload: {data:02x?}");
+/// [Transport] implementation for [BleTransport]
+#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+impl Transport for BleTransport {
+    type Filters = BleFilters;
+    type Info = BleInfo;
+    type Device = BleDevice;
 
-        // Write APDU in chunks
-        for (i, c) in data.chunks(self.mtu as usize - BLE_HEADER_LEN).enumerate() {
-            // Setup chunk buffer
-            let mut buff = Vec::with_capacity(self.mtu as usize);
-            let cmd = match i == 0 {
-                true => cmd,
-                false => 0x03,
-            };
+    /// List BLE connected ledger devices
+    async fn list(&mut self, filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
+        self.list_cancellable(filters, CancellationToken::new())
+            .await
+    }
 
-            buff.push(cmd); // Command
-            buff.extend_from_slice(&(i as u16).to_be_bytes()); // Sequence ID
-            buff.extend_from_slice(c);
+    /// Wait for the first ledger device to be discovered, using adapter discovery
+    /// events rather than a fixed scan duration
+    async fn wait_for_device(
+        &mut self,
+        filters: Self::Filters,
+        timeout: Duration,
+    ) -> Result<LedgerInfo, Error> {
+        let (info, p) = self.wait_internal(&filters, timeout).await?;
 
-            debug!("Write chunk {i}: {:02x?}", buff);
+        debug!("Discovered device: {info:?}");
 
-            self.p
-                .write(&self.c_write, &buff, WriteType::WithResponse)
-                .await?;
+        // Record peripheral for connection and events
+        self.known.lock().unwrap().insert(p.id(), info.clone());
+        if !self.peripherals.iter().any(|(_, d)| d.id() == p.id()) {
+            self.peripherals.push((info.clone(), p));
         }
 
-        Ok(())
+        Ok(info)
     }
 
-    /// Helper to read response packet from notification channel
-    async fn read_data(
+    /// Connect to a specific ledger device
+    ///
+    /// Note: this _must_ follow a [Self::list] operation to match `info` with known peripherals,
+    /// see [BleTransport::connect_by_addr] to connect without scanning
+    ///
+    /// On first connection the OS may prompt the user to pair with the device, this must
+    /// be accepted for the connection to succeed.
+    async fn connect(&mut self, info: Self::Info) -> Result<Self::Device, Error> {
+        let (model, p) = self.find_peripheral(&info)?;
+
+        self.connect_peripheral(info, model, p, &mut "connect")
+            .await
+    }
+
+    /// Connect to a specific ledger device with a timeout bounding the connection sequence,
+    /// returning [Error::ConnectTimeout] with the phase that stalled if this elapses
+    async fn connect_timeout(
         &mut self,
-        mut notifications: Pin<Box<dyn Stream<Item = ValueNotification> + Send>>,
-    ) -> Result<Vec<u8>, Error> {
-        // Await first response
-        let v = match notifications.next().await {
-            Some(v) => v.value,
-            None => {
-                return Err(Error::Closed);
+        info: Self::Info,
+        timeout: Duration,
+    ) -> Result<Self::Device, Error> {
+        let (model, p) = self.find_peripheral(&info)?;
+
+        let mut phase = "connect";
+        let r =
+            runtime::timeout(timeout, self.connect_peripheral(info, model, p, &mut phase)).await;
+
+        match r {
+            Ok(r) => r,
+            Err(_) => {
+                warn!("Connection timeout during {phase}");
+                Err(Error::ConnectTimeout(phase))
             }
-        };
+        }
+    }
+}
 
-        debug!("RX: {:02x?}", v);
+/// Helper to map adapter events to [DeviceEvent]s for known peripherals
+fn map_event(
+    e: CentralEvent,
+    known: &HashMap<PeripheralId, LedgerInfo>,
+) -> Option<(PeripheralId, DeviceEvent)> {
+    match e {
+        CentralEvent::DeviceConnected(id) => {
+            let ev = DeviceEvent::Connected(known.get(&id)?.clone());
+            Some((id, ev))
+        }
+        CentralEvent::DeviceDisconnected(id) => {
+            let ev = DeviceEvent::Disconnected(known.get(&id)?.clone());
+            Some((id, ev))
+        }
+        _ => None,
+    }
+}
 
-        // Check response length is reasonable
-        if v.len() < 5 {
-            error!("response too short");
-            return Err(Error::UnexpectedResponse);
-        } else if v[0] != 0x05 {
-            error!("unexpected response type: {:?}", v[0]);
-            return Err(Error::UnexpectedResponse);
+impl Drop for BleTransport {
+    fn drop(&mut self) {
+        for h in self.event_tasks.drain(..) {
+            h.abort();
         }
+    }
+}
+
+/// Helper to match a device model using advertised service UUIDs,
+/// falling back to the advertised name
+fn match_model(specs: &[BleSpec], services: &[Uuid], name: Option<&str>) -> Option<Model> {
+    // Match on advertised services (robust to renamed devices)
+    if let Some(s) = specs.iter().find(|s| services.contains(&s.service_uuid)) {
+        return Some(s.model.clone());
+    }
+
+    // Otherwise match on peripheral names
+    let name = name?;
+    specs
+        .iter()
+        .find(|s| !s.name.is_empty() && name.contains(s.name))
+        .map(|s| s.model.clone())
+}
 
-        // Read out full response length
-        let len = v[4] as usize;
-        if len == 0 {
-            return Err(Error::EmptyResponse);
+/// Helper to merge newly listed peripherals with those from a previous scan,
+/// keeping previous entries where `keep` is true and these were not re-discovered
+fn merge_peripherals<T, K: PartialEq>(
+    prev: Vec<T>,
+    mut next: Vec<T>,
+    id: impl Fn(&T) -> K,
+    keep: impl Fn(&K) -> bool,
+) -> Vec<T> {
+    for p in prev {
+        let k = id(&p);
+        if keep(&k) && !next.iter().any(|n| id(n) == k) {
+            next.push(p);
         }
+    }
+    next
+}
 
-        trace!("Expecting response length: {}", len);
+/// Helper to remove duplicate peripherals, keeping the latest entry for each
+/// in order of first discovery
+fn dedupe_peripherals<T, K: PartialEq>(items: Vec<T>, id: impl Fn(&T) -> K) -> Vec<T> {
+    let mut out: Vec<T> = Vec::with_capacity(items.len());
 
-        // Setup response buffer
-        let mut buff = Vec::with_capacity(len);
-        buff.extend_from_slice(&v[5..]);
+    for i in items {
+        let k = id(&i);
+        match out.iter_mut().find(|o| id(o) == k) {
+            Some(o) => *o = i,
+            None => out.push(i),
+        }
+    }
 
-        // Read further responses
-        // TODO: check this is correct with larger packets
-        while buff.len() < len {
-            // Await response notification
-            let v = match notifications.next().await {
-                Some(v) => v.value,
-                None => {
-                    error!("Failed to fetch next chunk from peripheral");
-                    self.p.unsubscribe(&self.c_read).await?;
-                    return Err(Error::Closed);
-                }
-            };
+    out
+}
+
+/// Helper to discover services and match read / write characteristics for a device
+///
+/// Some platforms (eg. CoreBluetooth) may report characteristics incrementally,
+/// so these are accumulated until both are found or [BLE_DISCOVERY_TIMEOUT] elapses.
+async fn discover_characteristics(
+    p: &btleplug::platform::Peripheral,
+    specs: &BleSpec,
+) -> Result<(Characteristic, Characteristic), Error> {
+    p.discover_services().await?;
+
+    let (mut c_write, mut c_read) = (None, None);
+    let deadline = std::time::Instant::now() + BLE_DISCOVERY_TIMEOUT;
 
-            debug!("RX: {v:02x?}");
+    loop {
+        let characteristics = p.characteristics();
+
+        trace!("Characteristics: {characteristics:?}");
 
-            // TODO: check sequence index?
+        match_characteristics(&characteristics, specs, &mut c_write, &mut c_read);
 
-            // add received data to buffer
-            buff.extend_from_slice(&v[5..]);
+        match (&c_write, &c_read) {
+            (Some(w), Some(r)) => return Ok((w.clone(), r.clone())),
+            _ if std::time::Instant::now() >= deadline => {
+                debug!("Characteristic discovery timed out (write: {c_write:?}, read: {c_read:?})");
+                return Err(Error::CharacteristicNotFound);
+            }
+            _ => runtime::sleep(BLE_DISCOVERY_POLL_INTERVAL).await,
         }
+    }
+}
 
-        Ok(buff)
+/// Helper to match write and notify characteristics, retaining previous matches
+fn match_characteristics<'a>(
+    characteristics: impl IntoIterator<Item = &'a Characteristic>,
+    specs: &BleSpec,
+    c_write: &mut Option<Characteristic>,
+    c_read: &mut Option<Characteristic>,
+) {
+    for c in characteristics {
+        if c.uuid == specs.write_uuid && c_write.is_none() {
+            *c_write = Some(c.clone());
+        } else if c.uuid == specs.notify_uuid && c_read.is_none() {
+            *c_read = Some(c.clone());
+        }
+    }
+}
+
+/// Maximum time to wait for required characteristics to be reported
+const BLE_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Interval between characteristic checks during discovery
+const BLE_DISCOVERY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Helper to select a write type supported by a characteristic, preferring writes with response
+///
+/// Some firmware revisions only support writes without response on the write characteristic.
+fn select_write_type(properties: CharPropFlags) -> WriteType {
+    if !properties.contains(CharPropFlags::WRITE)
+        && properties.contains(CharPropFlags::WRITE_WITHOUT_RESPONSE)
+    {
+        WriteType::WithoutResponse
+    } else {
+        WriteType::WithResponse
+    }
+}
+
+/// Delay prior to retrying discovery, allowing OS pairing / bonding to complete
+const BLE_PAIRING_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// GAP Device Name characteristic (0x2A00)
+const GAP_DEVICE_NAME_UUID: Uuid = uuid!("00002a00-0000-1000-8000-00805f9b34fb");
+
+/// Command tag for MTU request / response packets
+const BLE_CMD_MTU: u8 = 0x08;
+
+/// Default BLE MTU, used where negotiation fails
+const BLE_DEFAULT_MTU: u8 = 23;
+
+/// Maximum delay between frames of a multi-frame response, allows stalled transfers
+/// to be detected prior to the overall exchange timeout
+const BLE_FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Notification stream for BLE responses
+type Notifications = Pin<Box<dyn Stream<Item = ValueNotification> + Send>>;
+
+/// Explicit subscription state, ensuring subscription occurs exactly once
+/// (until reset, for example when the underlying stream closes)
+struct Subscription<S> {
+    stream: Option<S>,
+}
+
+impl<S> Default for Subscription<S> {
+    fn default() -> Self {
+        Self { stream: None }
+    }
+}
+
+impl<S> Subscription<S> {
+    /// Fetch the active subscription, calling `subscribe` if not yet subscribed
+    async fn get_or_subscribe<F, Fut>(&mut self, subscribe: F) -> Result<&mut S, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<S, Error>>,
+    {
+        if self.stream.is_none() {
+            self.stream = Some(subscribe().await?);
+        }
+
+        Ok(self.stream.as_mut().unwrap())
+    }
+
+    /// Fetch the active subscription if subscribed
+    fn get_mut(&mut self) -> Option<&mut S> {
+        self.stream.as_mut()
+    }
+
+    /// Clear subscription state
+    fn reset(&mut self) {
+        self.stream = None;
+    }
+}
+
+/// Response reassembly state, held outside of [BleDevice::read_data] so partial
+/// responses can be reported on timeout
+#[derive(Default)]
+struct RxState {
+    /// Data received so far
+    buff: Vec<u8>,
+    /// Declared response length (zero prior to receipt of the first frame)
+    expected: usize,
+}
+
+impl BleDevice {
+    /// Helper to write commands as chunks based on device MTU
+    ///
+    /// Continuation frames are tagged using the configured continuation tag,
+    /// see [BleDevice::set_frame_tags].
+    async fn write_command(&mut self, cmd: u8, payload: &[u8]) -> Result<(), Error> {
+        trace!(
+            "TX cmd: 0x{cmd:02x} payload: {:?}",
+            Payload::new(payload, self.log_limit)
+        );
+
+        // Write APDU in chunks
+        for (i, buff) in encode_frames(cmd, self.tags.1, payload, self.mtu)
+            .iter()
+            .enumerate()
+        {
+            // Delay between chunks where configured
+            if let (Some(d), true) = (self.write_chunk_delay, i > 0) {
+                runtime::sleep(d).await;
+            }
+
+            trace!("Write chunk {i}: {:?}", Payload::new(buff, self.log_limit));
+
+            self.p.write(&self.c_write, buff, self.write_type).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Helper to read response packet from notification channel
+    ///
+    /// `cmd` is the expected response tag, matching the command that was sent
+    /// (eg. the APDU tag for APDU exchanges or [BLE_CMD_MTU] for MTU requests).
+    async fn read_data(&mut self, cmd: u8, rx: &mut RxState) -> Result<Vec<u8>, Error> {
+        let (max_len, log_limit) = (self.max_response_len, self.log_limit);
+        let n = self.subscribe().await?;
+
+        let r = read_frames(
+            &mut n.map(|v| v.value),
+            cmd,
+            rx,
+            BLE_FRAME_TIMEOUT,
+            max_len,
+            log_limit,
+        )
+        .await;
+
+        // Clear subscription if the notification stream has closed
+        if let Err(Error::Closed) = r {
+            self.notifications.reset();
+        }
+
+        r
+    }
+
+    /// Subscribe to response notifications, if not already subscribed
+    async fn subscribe(&mut self) -> Result<&mut Notifications, Error> {
+        let (p, c) = (&self.p, &self.c_read);
+
+        self.notifications
+            .get_or_subscribe(|| async move {
+                debug!("Subscribing to notifications");
+                p.subscribe(c).await?;
+                Ok(p.notifications().await?)
+            })
+            .await
+    }
+
+    /// Discard any pending notifications, returning the number of frames dropped
+    ///
+    /// Notifications may arrive after an exchange has completed or been abandoned
+    /// (for example a late response following a timeout or cancellation), and would
+    /// otherwise be consumed as the response to the next request. This is called
+    /// prior to each exchange and does not block.
+    pub fn drain_events(&mut self) -> usize {
+        let log_limit = self.log_limit;
+
+        match self.notifications.get_mut() {
+            Some(n) => drain_frames(&mut n.map(|v| v.value), log_limit),
+            None => 0,
+        }
     }
 
     /// Helper to fetch the available MTU from a bluetooth device
     async fn fetch_mtu(&mut self) -> Result<u8, Error> {
-        // Setup read characteristic subscription
-        self.p.subscribe(&self.c_read).await?;
-        let mut n = self.p.notifications().await?;
-
         // Write get mtu command
-        self.write_command(0x08, &[]).await?;
+        self.write_command(BLE_CMD_MTU, &[]).await?;
 
         // Await MTU response
-        let mtu = match n.next().await {
-            Some(r) if r.value[0] == 0x08 && r.value.len() == 6 => {
-                debug!("RX: {:02x?}", r);
-                r.value[5]
-            }
-            Some(r) => {
-                warn!("Unexpected MTU response: {r:02x?}");
-                return Err(Error::Unknown);
-            }
-            None => {
-                warn!("Failed to request MTU");
-                return Err(Error::Unknown);
+        let mtu = match self.read_data(BLE_CMD_MTU, &mut RxState::default()).await {
+            Ok(r) => parse_mtu(&r)?,
+            Err(e) => {
+                warn!("Failed to request MTU: {e:?}");
+                return Err(e);
             }
         };
 
-        // Unsubscribe from characteristic
-        self.p.unsubscribe(&self.c_read).await?;
-
         Ok(mtu)
     }
 
+    /// Fetch the model of this device (matched on connection)
+    pub fn model(&self) -> Model {
+        self.model.clone()
+    }
+
+    /// Fetch the platform peripheral ID for this device
+    ///
+    /// This is stable across sessions (a UUID on macOS / iOS, a device path under
+    /// BlueZ) and may be persisted for use with [BleTransport::connect_by_id].
+    pub fn id(&self) -> PeripheralId {
+        self.p.id()
+    }
+
+    /// Fetch the MTU in use for this device
+    ///
+    /// This is negotiated with the device on connection, falling back to the
+    /// BLE default of 23 bytes if negotiation fails.
+    pub fn mtu(&self) -> u8 {
+        self.mtu
+    }
+
+    /// Fetch transport parameters negotiated on connection
+    pub fn capabilities(&self) -> BleCapabilities {
+        BleCapabilities {
+            mtu: self.mtu,
+            write_without_response: self.write_type == WriteType::WithoutResponse,
+            model: self.model.clone(),
+        }
+    }
+
+    /// Benchmark round-trip exchanges at the current MTU,
+    /// see [diagnostics::benchmark](crate::diagnostics::benchmark)
+    pub async fn benchmark(
+        &mut self,
+        payload_size: usize,
+        rounds: usize,
+    ) -> Result<BenchReport, Error> {
+        let mut r = benchmark(self, payload_size, rounds).await?;
+        r.mtu = Some(self.mtu);
+        Ok(r)
+    }
+
+    /// Set the maximum number of payload bytes included in trace output
+    /// (defaults to [DEFAULT_LOG_PAYLOAD_LIMIT])
+    pub fn set_log_payload_limit(&mut self, limit: usize) {
+        self.log_limit = limit;
+    }
+
+    /// Set a delay inserted between chunks of multi-frame writes (disabled by default)
+    ///
+    /// This is a workaround for BLE stacks (eg. some Android and older iOS devices)
+    /// that drop writes sent in quick succession, which appears as hangs or intermittent
+    /// [Error::EmptyResponse] / [Error::Timeout] failures on longer APDUs.
+    /// A few milliseconds is typically sufficient.
+    pub fn set_write_chunk_delay(&mut self, delay: Option<Duration>) {
+        self.write_chunk_delay = delay;
+    }
+
+    /// Set the command tags used for APDU exchanges, for relays or protocol variants
+    /// using non-standard framing (defaults to [BLE_CMD_APDU] / [BLE_CMD_CONTINUATION])
+    ///
+    /// `command` tags the first frame of each command and all response frames,
+    /// `continuation` tags subsequent frames of multi-frame commands.
+    pub fn set_frame_tags(&mut self, command: u8, continuation: u8) {
+        self.tags = (command, continuation);
+    }
+
+    /// Request a preferred connection interval from the platform BLE stack
+    ///
+    /// Connection parameters are negotiated by the central and are not exposed by
+    /// `btleplug` on any supported platform (BlueZ, CoreBluetooth, WinRT), so this
+    /// is currently a no-op that always succeeds. This allows cross-platform code to
+    /// call it unconditionally, with support added where platform APIs become available.
+    pub async fn request_connection_priority(
+        &mut self,
+        priority: ConnectionPriority,
+    ) -> Result<(), Error> {
+        debug!(
+            "Connection priority {priority:?} requested for {}, not supported on this platform",
+            self.info
+        );
+        Ok(())
+    }
+
+    /// Set the maximum accepted response length (defaults to [DEFAULT_MAX_RESPONSE_LEN]),
+    /// responses declaring a larger length fail with [Error::ResponseTooLarge]
+    pub fn set_max_response_len(&mut self, len: usize) {
+        self.max_response_len = len;
+    }
+
+    /// Set a callback to be invoked for each APDU exchanged with the device
+    ///
+    /// This fires once per command / response, not per BLE frame.
+    pub fn set_apdu_logger(&mut self, f: impl Fn(Direction, &[u8]) + Send + Sync + 'static) {
+        self.logger = Some(Arc::new(f));
+    }
+
+    /// Set a [Metrics](crate::metrics::Metrics) handle to be invoked for each exchange
+    pub fn set_metrics(&mut self, metrics: Option<MetricsHandle>) {
+        self.metrics = metrics;
+    }
+
     pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
         let c = self.p.is_connected().await?;
         Ok(c)
     }
+
+    /// Enable or disable a background keepalive for idle connections.
+    ///
+    /// When enabled a harmless MTU request is written to the device every `interval`
+    /// to prevent idle BLE links from being dropped. Pings are skipped while an
+    /// exchange is in progress.
+    pub fn set_keepalive(&mut self, interval: Option<Duration>) {
+        // Stop any existing keepalive task
+        if let Some(h) = self.keepalive.take() {
+            h.abort();
+        }
+
+        let interval = match interval {
+            Some(v) => v,
+            None => return,
+        };
+
+        debug!("Enabling keepalive for {} ({interval:?})", self.info);
+
+        let p = self.p.clone();
+        let (c_write, write_type) = (self.c_write.clone(), self.write_type);
+        let busy = self.busy.clone();
+
+        self.keepalive = Some(tokio::task::spawn(async move {
+            let mut t = runtime::interval(interval);
+            t.tick().await;
+
+            loop {
+                t.tick().await;
+
+                // Skip pings while an exchange is in progress
+                let _guard = match busy.try_lock() {
+                    Ok(g) => g,
+                    Err(_) => continue,
+                };
+
+                // Write MTU request (cmd 0x08, seq: 0x0000, len: 0x0000)
+                let ping = [BLE_CMD_MTU, 0x00, 0x00, 0x00, 0x00];
+
+                trace!("Keepalive ping");
+
+                if let Err(e) = p.write(&c_write, &ping, write_type).await {
+                    warn!("Keepalive ping failed: {e:?}");
+                }
+            }
+        }));
+    }
+
+    /// Internal APDU exchange, wrapped in a tracing span by [Exchange::exchange]
+    async fn exchange_inner(
+        &mut self,
+        command: &[u8],
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        // Mark device as busy to pause keepalive pings
+        let busy = self.busy.clone();
+        let _guard = busy.lock().await;
+
+        let metrics = self.metrics.clone();
+        with_metrics(
+            &metrics,
+            ConnType::Ble,
+            command,
+            self.transact(command, timeout),
+        )
+        .await
+    }
+
+    /// Exchange a sequence of APDUs, see [Exchange::exchange_many]
+    async fn exchange_many_inner(
+        &mut self,
+        commands: &[Vec<u8>],
+        timeout: Duration,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        // Mark device as busy to pause keepalive pings
+        let busy = self.busy.clone();
+        let _guard = busy.lock().await;
+
+        let mut responses = Vec::with_capacity(commands.len());
+        for c in commands {
+            let r = self.transact(c, timeout).await?;
+
+            // Stop on the first failed command
+            let ok = is_success(&r);
+            responses.push(r);
+            if !ok {
+                break;
+            }
+        }
+
+        Ok(responses)
+    }
+
+    /// Helper to write a single APDU and await the response
+    async fn transact(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        debug!("TX: {:?}", Payload::new(command, self.log_limit));
+        log_apdu(&self.logger, Direction::Tx, command);
+
+        // Discard stale notifications (eg. keepalive or cancelled exchange responses)
+        self.drain_events();
+
+        // Write command data
+        let tag = self.tags.0;
+        self.write_command(tag, command).await?;
+
+        debug!("Await response");
+
+        // Wait for response
+        let mut rx = RxState::default();
+        let buff = match runtime::timeout(timeout, self.read_data(tag, &mut rx)).await {
+            Ok(r) => r?,
+            // Timeout with no data received
+            Err(e) if rx.buff.is_empty() => return Err(e),
+            // Timeout mid-response, return partial data for diagnostics
+            Err(_) => {
+                warn!(
+                    "Timeout with partial response ({} of {} bytes)",
+                    rx.buff.len(),
+                    rx.expected
+                );
+                return Err(Error::IncompleteResponse {
+                    received: rx.buff,
+                    expected: rx.expected,
+                });
+            }
+        };
+
+        debug!("RX: {:?}", Payload::new(&buff, self.log_limit));
+        log_apdu(&self.logger, Direction::Rx, &buff);
+
+        check_response(&buff)?;
+
+        Ok(buff)
+    }
+}
+
+/// [Drop] impl stops the keepalive task (if running), unsubscribes from notifications,
+/// and releases the peripheral for future connections when the [BleDevice] is dropped
+///
+/// The peripheral is released only once unsubscribed, so a new connection
+/// cannot be unsubscribed by a stale handle.
+impl Drop for BleDevice {
+    fn drop(&mut self) {
+        if let Some(h) = self.keepalive.take() {
+            h.abort();
+        }
+
+        let (id, connected) = (self.p.id(), self.connected.clone());
+        let release = move || {
+            if let Ok(mut c) = connected.lock() {
+                c.remove(&id);
+            }
+        };
+
+        // Release notification subscription where a runtime is available
+        let h = match tokio::runtime::Handle::try_current() {
+            Ok(h) if self.notifications.get_mut().is_some() => h,
+            _ => return release(),
+        };
+
+        let (p, c) = (self.p.clone(), self.c_read.clone());
+        h.spawn(async move {
+            if let Err(e) = p.unsubscribe(&c).await {
+                debug!("Failed to unsubscribe: {e:?}");
+            }
+            release();
+        });
+    }
 }
 
 /// [Exchange] impl for BLE backed devices
 #[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Exchange for BleDevice {
     async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
-        // Fetch notification channel for responses
-        self.p.subscribe(&self.c_read).await?;
-        let notifications = self.p.notifications().await?;
+        // Setup span for correlating exchange events
+        #[cfg(feature = "tracing")]
+        {
+            let span = debug_span!(
+                "exchange",
+                id = next_exchange_id(),
+                cla = command.first(),
+                ins = command.get(1),
+                len = command.len(),
+                peripheral = %self.p.id(),
+            );
+
+            self.exchange_inner(command, timeout).instrument(span).await
+        }
 
-        // Write command data
-        if let Err(e) = self.write_command(0x05, command).await {
-            self.p.unsubscribe(&self.c_read).await?;
+        #[cfg(not(feature = "tracing"))]
+        self.exchange_inner(command, timeout).await
+    }
+
+    /// Exchange a sequence of APDUs, retaining the notification subscription
+    /// across the batch
+    async fn exchange_many(
+        &mut self,
+        commands: &[Vec<u8>],
+        timeout: Duration,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        #[cfg(feature = "tracing")]
+        {
+            let span = debug_span!(
+                "exchange_many",
+                id = next_exchange_id(),
+                count = commands.len(),
+                peripheral = %self.p.id(),
+            );
+
+            self.exchange_many_inner(commands, timeout)
+                .instrument(span)
+                .await
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        self.exchange_many_inner(commands, timeout).await
+    }
+
+    /// BLE frames carry up to `MTU - 3` bytes of payload
+    fn max_payload_chunk(&self) -> usize {
+        (self.mtu as usize).saturating_sub(BLE_HEADER_LEN).max(1)
+    }
+}
+
+/// Helper to check whether a scan failure indicates the adapter is missing or powered off
+///
+/// `btleplug` does not expose adapter state, so powered off adapters are detected via
+/// the BlueZ `org.bluez.Error.NotReady` error returned when starting discovery.
+fn is_adapter_unavailable(e: &btleplug::Error) -> bool {
+    match e {
+        btleplug::Error::DeviceNotFound => true,
+        btleplug::Error::Other(e) => e.to_string().contains("NotReady"),
+        _ => false,
+    }
+}
+
+/// Helper to parse an MTU response, rejecting MTUs too small to carry frame data
+fn parse_mtu(r: &[u8]) -> Result<u8, Error> {
+    match r {
+        [mtu] if *mtu as usize > BLE_HEADER_LEN => Ok(*mtu),
+        _ => {
+            warn!("Unexpected MTU response: {r:02x?}");
+            Err(Error::Unknown)
+        }
+    }
+}
+
+/// Helper to discard frames that are already available on a notification stream
+fn drain_frames<S: Stream<Item = Vec<u8>> + Unpin>(s: &mut S, log_limit: usize) -> usize {
+    let mut n = 0;
+
+    while let Some(Some(v)) = s.next().now_or_never() {
+        trace!(
+            "Discarding stale notification: {:?}",
+            Payload::new(&v, log_limit)
+        );
+        n += 1;
+    }
+
+    n
+}
+
+/// Helper to reassemble a response from notification frames,
+/// returning [Error::Closed] if the stream ends prior to completion or
+/// [Error::IncompleteResponse] (with the partial response) if a continuation frame
+/// is not received within `frame_timeout`
+async fn read_frames<S: Stream<Item = Vec<u8>> + Unpin>(
+    s: &mut S,
+    cmd: u8,
+    rx: &mut RxState,
+    frame_timeout: Duration,
+    max_len: usize,
+    log_limit: usize,
+) -> Result<Vec<u8>, Error> {
+    // Await first response, skipping stray frames (eg. late continuations or
+    // responses to other commands) until the response header is received
+    let v = loop {
+        let v = match s.next().await {
+            Some(v) => v,
+            None => {
+                return Err(Error::Closed);
+            }
+        };
+
+        trace!("RX chunk: {:?}", Payload::new(&v, log_limit));
+
+        if v.len() >= BLE_HEADER_LEN && (v[0] != cmd || v[1..3] != [0, 0]) {
+            warn!(
+                "Skipping unexpected frame (tag: {:?}, expected {:?}, seq: {:?})",
+                v[0],
+                cmd,
+                &v[1..3]
+            );
+            continue;
+        }
+
+        break v;
+    };
+
+    // Read out full response length (2-byte big endian following tag and sequence)
+    let (len, data) = match decode_header(&v) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("Invalid response header: {e}");
             return Err(e);
         }
+    };
+    if len > max_len {
+        error!("response length {len} exceeds maximum ({max_len})");
+        return Err(Error::ResponseTooLarge { len, max: max_len });
+    }
 
-        debug!("Await response");
+    trace!("Expecting response length: {}", len);
 
-        // Wait for response
-        let buff = match tokio::time::timeout(timeout, self.read_data(notifications)).await {
-            Ok(Ok(v)) => v,
-            Ok(Err(e)) => {
-                self.p.unsubscribe(&self.c_read).await?;
-                return Err(e);
+    // Setup response buffer
+    rx.expected = len;
+    rx.buff = Vec::with_capacity(len);
+    rx.buff.extend_from_slice(data);
+
+    let mut frames = vec![v];
+
+    // Read further responses
+    while rx.buff.len() < len {
+        // Await response notification
+        let v = match runtime::timeout(frame_timeout, s.next()).await {
+            Ok(Some(v)) => v,
+            Err(e) => {
+                warn!(
+                    "Timeout awaiting continuation frame ({} of {len} bytes)",
+                    rx.buff.len()
+                );
+                if rx.buff.is_empty() {
+                    return Err(e);
+                }
+                return Err(Error::IncompleteResponse {
+                    received: rx.buff.clone(),
+                    expected: len,
+                });
+            }
+            Ok(None) => {
+                error!("Failed to fetch next chunk from peripheral");
+                return Err(Error::Closed);
             }
+        };
+
+        trace!("RX chunk: {:?}", Payload::new(&v, log_limit));
+
+        // Add received data to buffer, checking the sequence index
+        match decode_continuation(&v, frames.len() as u16) {
+            Ok(d) => rx.buff.extend_from_slice(d),
             Err(e) => {
-                self.p.unsubscribe(&self.c_read).await?;
-                return Err(e.into());
+                error!("Invalid continuation frame {}: {e}", frames.len());
+                return Err(e);
+            }
+        }
+
+        frames.push(v);
+    }
+
+    rx.buff.clear();
+
+    decode_response(&frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribe_once() {
+        let mut s = Subscription::default();
+        let mut count = 0;
+
+        for _ in 0..3 {
+            s.get_or_subscribe(|| async {
+                count += 1;
+                Ok(count)
+            })
+            .await
+            .unwrap();
+        }
+        assert_eq!(count, 1);
+        assert_eq!(s.get_mut(), Some(&mut 1));
+
+        // Resubscribe after reset
+        s.reset();
+        assert_eq!(s.get_mut(), None);
+
+        s.get_or_subscribe(|| async { Ok(2) }).await.unwrap();
+        assert_eq!(s.get_mut(), Some(&mut 2));
+    }
+
+    #[tokio::test]
+    async fn drain_stale_frames() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+
+        // Late response from a previous (timed out) exchange
+        tx.unbounded_send(vec![BLE_CMD_APDU, 0x00, 0x00, 0x00, 0x02, 0x6e, 0x00])
+            .unwrap();
+
+        assert_eq!(drain_frames(&mut rx, DEFAULT_LOG_PAYLOAD_LIMIT), 1);
+        assert_eq!(drain_frames(&mut rx, DEFAULT_LOG_PAYLOAD_LIMIT), 0);
+
+        // Response to the current exchange, split over two frames
+        tx.unbounded_send(vec![BLE_CMD_APDU, 0x00, 0x00, 0x00, 0x04, 0x01, 0x02])
+            .unwrap();
+        tx.unbounded_send(vec![BLE_CMD_APDU, 0x00, 0x01, 0x90, 0x00])
+            .unwrap();
+
+        let r = read_frames(
+            &mut rx,
+            BLE_CMD_APDU,
+            &mut RxState::default(),
+            BLE_FRAME_TIMEOUT,
+            DEFAULT_MAX_RESPONSE_LEN,
+            DEFAULT_LOG_PAYLOAD_LIMIT,
+        )
+        .await
+        .unwrap();
+        assert_eq!(r, vec![0x01, 0x02, 0x90, 0x00]);
+
+        // Stream closure is reported
+        drop(tx);
+        let r = read_frames(
+            &mut rx,
+            BLE_CMD_APDU,
+            &mut RxState::default(),
+            BLE_FRAME_TIMEOUT,
+            DEFAULT_MAX_RESPONSE_LEN,
+            DEFAULT_LOG_PAYLOAD_LIMIT,
+        )
+        .await;
+        assert!(matches!(r, Err(Error::Closed)));
+    }
+
+    #[tokio::test]
+    async fn skip_stray_frames() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+
+        // Stray continuation and MTU response frames ahead of the response header
+        tx.unbounded_send(vec![BLE_CMD_APDU, 0x00, 0x01, 0xaa, 0xbb])
+            .unwrap();
+        tx.unbounded_send(vec![BLE_CMD_MTU, 0x00, 0x00, 0x00, 0x01, 0x99])
+            .unwrap();
+        tx.unbounded_send(vec![BLE_CMD_APDU, 0x00, 0x00, 0x00, 0x02, 0x90, 0x00])
+            .unwrap();
+
+        let r = read_frames(
+            &mut rx,
+            BLE_CMD_APDU,
+            &mut RxState::default(),
+            BLE_FRAME_TIMEOUT,
+            DEFAULT_MAX_RESPONSE_LEN,
+            DEFAULT_LOG_PAYLOAD_LIMIT,
+        )
+        .await
+        .unwrap();
+        assert_eq!(r, vec![0x90, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn response_too_large() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+
+        // Declared length exceeds the configured maximum
+        tx.unbounded_send(vec![BLE_CMD_APDU, 0x00, 0x00, 0x10, 0x01, 0x01, 0x02])
+            .unwrap();
+
+        let r = read_frames(
+            &mut rx,
+            BLE_CMD_APDU,
+            &mut RxState::default(),
+            BLE_FRAME_TIMEOUT,
+            DEFAULT_MAX_RESPONSE_LEN,
+            DEFAULT_LOG_PAYLOAD_LIMIT,
+        )
+        .await;
+        assert!(matches!(
+            r,
+            Err(Error::ResponseTooLarge {
+                len: 0x1001,
+                max: DEFAULT_MAX_RESPONSE_LEN
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn frame_timeout() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+
+        // First frame only, device stalls mid-response
+        tx.unbounded_send(vec![BLE_CMD_APDU, 0x00, 0x00, 0x00, 0x04, 0x01, 0x02])
+            .unwrap();
+
+        let mut state = RxState::default();
+        let r = read_frames(
+            &mut rx,
+            BLE_CMD_APDU,
+            &mut state,
+            Duration::from_millis(10),
+            DEFAULT_MAX_RESPONSE_LEN,
+            DEFAULT_LOG_PAYLOAD_LIMIT,
+        )
+        .await;
+
+        // Partial response is returned for diagnostics
+        match r {
+            Err(Error::IncompleteResponse { received, expected }) => {
+                assert_eq!(received, vec![0x01, 0x02]);
+                assert_eq!(expected, 4);
             }
+            r => panic!("unexpected result: {r:?}"),
+        }
+        assert_eq!(state.buff, vec![0x01, 0x02]);
+        assert_eq!(state.expected, 4);
+    }
+
+    #[test]
+    fn scan_errors() {
+        assert!(is_adapter_unavailable(&btleplug::Error::DeviceNotFound));
+        assert!(is_adapter_unavailable(&btleplug::Error::Other(
+            "org.bluez.Error.NotReady: Resource Not Ready".into()
+        )));
+
+        // Other failures are passed through
+        assert!(!is_adapter_unavailable(&btleplug::Error::NotSupported(
+            "scan".to_string()
+        )));
+        assert!(!is_adapter_unavailable(&btleplug::Error::Other(
+            "org.bluez.Error.InProgress: Operation already in progress".into()
+        )));
+    }
+
+    #[test]
+    fn mtu_response() {
+        assert_eq!(parse_mtu(&[0x99]).unwrap(), 0x99);
+
+        // MTUs without space for frame data are rejected (falling back to the default)
+        for r in [&[][..], &[0x00], &[0x03], &[0x17, 0x00]] {
+            assert!(matches!(parse_mtu(r), Err(Error::Unknown)), "{r:02x?}");
+        }
+    }
+
+    #[test]
+    fn match_renamed_device() {
+        let addr = BDAddr::from([0x01, 0x02, 0x03, 0x04, 0xab, 0xcd]);
+
+        // Info from a listing, prior to the GAP name being read on connection
+        let listed = BleInfo::new("Nano X".to_string(), addr);
+        let renamed = BleInfo::new("Nano X ABCD".to_string(), addr);
+        assert!(renamed.same_device(&listed));
+        assert!(listed.same_device(&renamed));
+
+        let other = BleInfo::new("Nano X".to_string(), BDAddr::from([0x01; 6]));
+        assert!(!other.same_device(&listed));
+
+        // Devices without addresses are matched by name
+        let a = BleInfo::new("Nano X A".to_string(), BDAddr::default());
+        let b = BleInfo::new("Nano X B".to_string(), BDAddr::default());
+        assert!(a.same_device(&a.clone()));
+        assert!(!a.same_device(&b));
+
+        // Or by peripheral ID where available
+        let a = BleInfo {
+            id: Some("a".to_string()),
+            ..a
+        };
+        let renamed = BleInfo {
+            name: "Nano X ABCD".to_string(),
+            ..a.clone()
+        };
+        let b = BleInfo {
+            id: Some("b".to_string()),
+            ..a.clone()
+        };
+        assert!(renamed.same_device(&a));
+        assert!(!b.same_device(&a));
+    }
+
+    #[test]
+    fn info_stable_id() {
+        let i = BleInfo::new("Nano X".to_string(), BDAddr::from([1, 2, 3, 4, 5, 6]));
+        assert_eq!(i.stable_id().as_deref(), Some("01:02:03:04:05:06"));
+
+        // Zeroed addresses (as reported on macOS / iOS) are not used
+        let i = BleInfo::new("Nano X".to_string(), BDAddr::default());
+        assert_eq!(i.stable_id(), None);
+
+        let i = BleInfo {
+            id: Some("8e2e2a4c-6b4f-4b0c-9a43-0e6f3f0e5a11".to_string()),
+            ..i
+        };
+        assert_eq!(
+            i.stable_id().as_deref(),
+            Some("8e2e2a4c-6b4f-4b0c-9a43-0e6f3f0e5a11")
+        );
+    }
+
+    #[test]
+    fn redact_info_debug() {
+        let i = BleInfo::new(
+            "Nano X".to_string(),
+            BDAddr::from([0x01, 0x02, 0x03, 0x04, 0xab, 0xcd]),
+        );
+
+        assert_eq!(
+            format!("{i:?}"),
+            r#"BleInfo { name: "Nano X", addr: **:**:**:**:AB:CD }"#
+        );
+        assert_eq!(
+            i.full_debug(),
+            r#"BleInfo { name: "Nano X", addr: 01:02:03:04:AB:CD }"#
+        );
+    }
+
+    #[test]
+    fn info_ignores_adv_state() {
+        let a = BleInfo::new("Nano X".to_string(), BDAddr::from([1, 2, 3, 4, 5, 6]));
+        let b = BleInfo {
+            adv: Some(BleAdvState {
+                company_id: 0x02da,
+                data: vec![0x01],
+            }),
+            ..a.clone()
         };
 
-        Ok(buff)
+        assert_eq!(a, b);
+        assert_eq!(b.adv_state().map(|s| s.company_id), Some(0x02da));
+    }
+
+    #[test]
+    fn write_type_fallback() {
+        let tests = [
+            (CharPropFlags::WRITE, WriteType::WithResponse),
+            (
+                CharPropFlags::WRITE | CharPropFlags::WRITE_WITHOUT_RESPONSE,
+                WriteType::WithResponse,
+            ),
+            (
+                CharPropFlags::WRITE_WITHOUT_RESPONSE,
+                WriteType::WithoutResponse,
+            ),
+            (CharPropFlags::empty(), WriteType::WithResponse),
+        ];
+
+        for (p, w) in tests {
+            assert_eq!(select_write_type(p), w, "{p:?}");
+        }
+    }
+
+    #[test]
+    fn scan_filters() {
+        assert!(BleFilters::default()
+            .scan_filter(BLE_SPECS)
+            .services
+            .is_empty());
+
+        let f = BleFilters {
+            filter_services: true,
+            ..Default::default()
+        };
+        assert_eq!(f.scan_filter(BLE_SPECS).services.len(), BLE_SPECS.len());
+    }
+
+    #[test]
+    fn match_models() {
+        let stax_service = uuid!("13d63400-2c97-6004-0000-4c6564676572");
+
+        // Service UUIDs take precedence over names
+        assert_eq!(
+            match_model(BLE_SPECS, &[stax_service], Some("My Ledger")),
+            Some(Model::Stax)
+        );
+        assert_eq!(
+            match_model(BLE_SPECS, &[stax_service], None),
+            Some(Model::Stax)
+        );
+
+        // Fallback to name matching
+        assert_eq!(
+            match_model(BLE_SPECS, &[], Some("Nano X 1A2B")),
+            Some(Model::NanoX)
+        );
+        assert_eq!(match_model(BLE_SPECS, &[], Some("Headphones")), None);
+        assert_eq!(match_model(BLE_SPECS, &[], None), None);
+
+        // Registered specs are matched by service UUID
+        let custom = BleSpec {
+            model: Model::Unknown(0x7000),
+            name: "Custom",
+            service_uuid: uuid!("13d63400-2c97-7004-0000-4c6564676572"),
+            notify_uuid: uuid!("13d63400-2c97-7004-0001-4c6564676572"),
+            write_uuid: uuid!("13d63400-2c97-7004-0002-4c6564676572"),
+            write_cmd_uuid: uuid!("13d63400-2c97-7004-0003-4c6564676572"),
+        };
+        let specs = [std::slice::from_ref(&custom), BLE_SPECS].concat();
+        assert_eq!(
+            match_model(&specs, &[custom.service_uuid], None),
+            Some(Model::Unknown(0x7000))
+        );
+
+        // and by name
+        assert_eq!(
+            match_model(&specs, &[], Some("Custom 1234")),
+            Some(Model::Unknown(0x7000))
+        );
+    }
+
+    #[test]
+    fn merge_retains_connected() {
+        let prev = vec![(1, "a"), (2, "b"), (3, "c")];
+        let next = vec![(2, "b2"), (4, "d")];
+
+        // Peripherals 1 and 2 are connected, 1 was not re-discovered and is retained,
+        // 2 was re-discovered so the new entry is used, 3 is dropped
+        let m = merge_peripherals(prev, next, |(id, _)| *id, |id| [1, 2].contains(id));
+
+        assert_eq!(m, vec![(2, "b2"), (4, "d"), (1, "a")]);
+    }
+
+    #[test]
+    fn accumulate_characteristics() {
+        let spec = &BLE_SPECS[0];
+        let c = |uuid| Characteristic {
+            uuid,
+            service_uuid: spec.service_uuid,
+            properties: CharPropFlags::empty(),
+        };
+        let (mut w, mut r) = (None, None);
+
+        // Characteristics reported across separate batches are accumulated
+        match_characteristics(&[c(spec.notify_uuid)], spec, &mut w, &mut r);
+        assert!(w.is_none() && r.is_some());
+
+        match_characteristics(
+            &[c(spec.write_cmd_uuid), c(spec.write_uuid)],
+            spec,
+            &mut w,
+            &mut r,
+        );
+        assert_eq!(w.map(|c| c.uuid), Some(spec.write_uuid));
+        assert_eq!(r.map(|c| c.uuid), Some(spec.notify_uuid));
+    }
+
+    #[test]
+    fn dedupe_keeps_latest() {
+        let items = vec![(1, "a"), (2, "b"), (1, "a2"), (3, "c"), (2, "b2")];
+        let d = dedupe_peripherals(items, |(id, _)| *id);
+
+        assert_eq!(d, vec![(1, "a2"), (2, "b2"), (3, "c")]);
     }
 }
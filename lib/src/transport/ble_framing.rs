@@ -0,0 +1,294 @@
+//! BLE APDU framing
+//!
+//! Commands and responses are prefixed with a 2-byte big endian length and split
+//! into frames of up to `MTU` bytes. Each frame carries a 1-byte command tag and a
+//! 2-byte big endian sequence index, with continuation frames of commands tagged
+//! [BLE_CMD_CONTINUATION] by default.
+
+use crate::Error;
+
+/// Frame header length (command tag and sequence index)
+pub(crate) const BLE_HEADER_LEN: usize = 3;
+
+/// Default command tag for APDU exchange frames
+pub const BLE_CMD_APDU: u8 = 0x05;
+
+/// Default command tag for continuation frames of multi-frame commands
+pub const BLE_CMD_CONTINUATION: u8 = 0x03;
+
+/// Split a command payload into frames for the provided MTU, with frames following
+/// the first tagged `continuation`
+pub(crate) fn encode_frames(cmd: u8, continuation: u8, payload: &[u8], mtu: u8) -> Vec<Vec<u8>> {
+    // Setup outgoing data (adds 2-byte big endian length prefix)
+    let mut data = Vec::with_capacity(payload.len() + 2);
+    data.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    data.extend_from_slice(payload);
+
+    let chunk_len = (mtu as usize).saturating_sub(BLE_HEADER_LEN).max(1);
+
+    data.chunks(chunk_len)
+        .enumerate()
+        .map(|(i, c)| {
+            let mut buff = Vec::with_capacity(BLE_HEADER_LEN + c.len());
+            buff.push(match i {
+                0 => cmd,
+                _ => continuation,
+            });
+            buff.extend_from_slice(&(i as u16).to_be_bytes());
+            buff.extend_from_slice(c);
+            buff
+        })
+        .collect()
+}
+
+/// Decode the first frame of a response, returning the declared response length
+/// and the data carried by this frame
+///
+/// Frames declaring a zero length but carrying a valid status word are treated as
+/// a status-only response, [Error::EmptyResponse] is returned for frames with
+/// neither.
+pub(crate) fn decode_header(frame: &[u8]) -> Result<(usize, &[u8]), Error> {
+    if frame.len() < BLE_HEADER_LEN + 2 {
+        return Err(Error::UnexpectedResponse);
+    }
+    if frame[1..3] != [0, 0] {
+        return Err(Error::UnexpectedResponse);
+    }
+
+    let len = u16::from_be_bytes([frame[3], frame[4]]) as usize;
+    let data = &frame[BLE_HEADER_LEN + 2..];
+
+    match (len, data) {
+        (0, [sw1, _, ..]) if is_status_word(*sw1) => Ok((2, data)),
+        (0, _) => Err(Error::EmptyResponse),
+        _ => Ok((len, data)),
+    }
+}
+
+/// Check whether `sw1` is within the ISO 7816 status word ranges (`0x61..=0x6F`, `0x90..=0x9F`)
+/// or the Ledger specific `0x55xx` range (eg. `0x5515` device locked)
+fn is_status_word(sw1: u8) -> bool {
+    matches!(sw1, 0x55 | 0x61..=0x6f | 0x90..=0x9f)
+}
+
+/// Decode a continuation frame of a response, checking the sequence index
+pub(crate) fn decode_continuation(frame: &[u8], seq: u16) -> Result<&[u8], Error> {
+    if frame.len() < BLE_HEADER_LEN {
+        return Err(Error::UnexpectedResponse);
+    }
+    if u16::from_be_bytes([frame[1], frame[2]]) != seq {
+        return Err(Error::UnexpectedResponse);
+    }
+
+    Ok(&frame[BLE_HEADER_LEN..])
+}
+
+/// Reassemble a complete response from received frames
+pub(crate) fn decode_response(frames: &[Vec<u8>]) -> Result<Vec<u8>, Error> {
+    let (first, rest) = frames.split_first().ok_or(Error::UnexpectedResponse)?;
+
+    let (len, data) = decode_header(first)?;
+    let mut buff = Vec::with_capacity(len);
+    buff.extend_from_slice(data);
+
+    for (i, f) in rest.iter().enumerate() {
+        if buff.len() >= len {
+            return Err(Error::UnexpectedResponse);
+        }
+        buff.extend_from_slice(decode_continuation(f, i as u16 + 1)?);
+    }
+
+    if buff.len() < len {
+        return Err(Error::UnexpectedResponse);
+    }
+
+    // Drop any trailing padding
+    buff.truncate(len);
+
+    Ok(buff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_single_frame() {
+        let f = encode_frames(
+            0x05,
+            BLE_CMD_CONTINUATION,
+            &[0xe0, 0x01, 0x00, 0x00, 0x00],
+            23,
+        );
+        assert_eq!(
+            f,
+            vec![vec![
+                0x05, 0x00, 0x00, 0x00, 0x05, 0xe0, 0x01, 0x00, 0x00, 0x00
+            ]]
+        );
+
+        // Empty payloads (eg. MTU requests) are sent as a length-only frame
+        assert_eq!(
+            encode_frames(0x08, BLE_CMD_CONTINUATION, &[], 23),
+            vec![vec![0x08, 0x00, 0x00, 0x00, 0x00]]
+        );
+    }
+
+    #[test]
+    fn encode_multi_frame() {
+        let payload: Vec<u8> = (0..10).collect();
+        let f = encode_frames(0x05, BLE_CMD_CONTINUATION, &payload, 8);
+
+        assert_eq!(
+            f,
+            vec![
+                vec![0x05, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x01, 0x02],
+                vec![0x03, 0x00, 0x01, 0x03, 0x04, 0x05, 0x06, 0x07],
+                vec![0x03, 0x00, 0x02, 0x08, 0x09],
+            ]
+        );
+    }
+
+    #[test]
+    fn encode_custom_tags() {
+        let payload: Vec<u8> = (0..6).collect();
+        let f = encode_frames(0x15, 0x13, &payload, 8);
+
+        assert_eq!(
+            f,
+            vec![
+                vec![0x15, 0x00, 0x00, 0x00, 0x06, 0x00, 0x01, 0x02],
+                vec![0x13, 0x00, 0x01, 0x03, 0x04, 0x05],
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_single_frame() {
+        let r = decode_response(&[vec![0x05, 0x00, 0x00, 0x00, 0x02, 0x90, 0x00]]).unwrap();
+        assert_eq!(r, vec![0x90, 0x00]);
+
+        // Trailing padding is dropped
+        let r = decode_response(&[vec![0x05, 0x00, 0x00, 0x00, 0x02, 0x90, 0x00, 0x00]]).unwrap();
+        assert_eq!(r, vec![0x90, 0x00]);
+    }
+
+    #[test]
+    fn decode_status_only() {
+        // Zero declared length with a trailing status word
+        let r = decode_response(&[vec![0x05, 0x00, 0x00, 0x00, 0x00, 0x90, 0x00]]).unwrap();
+        assert_eq!(r, vec![0x90, 0x00]);
+
+        let r = decode_response(&[vec![0x05, 0x00, 0x00, 0x00, 0x00, 0x6e, 0x00, 0x00]]).unwrap();
+        assert_eq!(r, vec![0x6e, 0x00]);
+
+        // Ledger specific status words (device locked)
+        let r = decode_response(&[vec![0x05, 0x00, 0x00, 0x00, 0x00, 0x55, 0x15]]).unwrap();
+        assert_eq!(r, vec![0x55, 0x15]);
+    }
+
+    #[test]
+    fn decode_multi_frame() {
+        let frames = vec![
+            vec![0x05, 0x00, 0x00, 0x00, 0x05, 0x01, 0x02],
+            vec![0x05, 0x00, 0x01, 0x03, 0x90],
+            vec![0x05, 0x00, 0x02, 0x00],
+        ];
+        assert_eq!(
+            decode_response(&frames).unwrap(),
+            vec![0x01, 0x02, 0x03, 0x90, 0x00]
+        );
+    }
+
+    #[test]
+    fn decode_short_frames() {
+        // Missing frames
+        assert!(matches!(
+            decode_response(&[]),
+            Err(Error::UnexpectedResponse)
+        ));
+
+        // Header too short to contain a length
+        assert!(matches!(
+            decode_response(&[vec![0x05, 0x00, 0x00, 0x00]]),
+            Err(Error::UnexpectedResponse)
+        ));
+
+        // Empty response
+        assert!(matches!(
+            decode_response(&[vec![0x05, 0x00, 0x00, 0x00, 0x00]]),
+            Err(Error::EmptyResponse)
+        ));
+        assert!(matches!(
+            decode_response(&[vec![0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]]),
+            Err(Error::EmptyResponse)
+        ));
+
+        // Continuation frame too short
+        assert!(matches!(
+            decode_response(&[vec![0x05, 0x00, 0x00, 0x00, 0x04, 0x01], vec![0x05, 0x00]]),
+            Err(Error::UnexpectedResponse)
+        ));
+
+        // Incomplete response
+        assert!(matches!(
+            decode_response(&[vec![0x05, 0x00, 0x00, 0x00, 0x04, 0x01, 0x02]]),
+            Err(Error::UnexpectedResponse)
+        ));
+
+        // Excess frames
+        assert!(matches!(
+            decode_response(&[
+                vec![0x05, 0x00, 0x00, 0x00, 0x02, 0x90, 0x00],
+                vec![0x05, 0x00, 0x01, 0x00],
+            ]),
+            Err(Error::UnexpectedResponse)
+        ));
+    }
+
+    #[test]
+    fn decode_bad_sequence() {
+        // First frame with non-zero sequence
+        assert!(matches!(
+            decode_response(&[vec![0x05, 0x00, 0x01, 0x00, 0x02, 0x90, 0x00]]),
+            Err(Error::UnexpectedResponse)
+        ));
+
+        // Skipped continuation
+        assert!(matches!(
+            decode_response(&[
+                vec![0x05, 0x00, 0x00, 0x00, 0x04, 0x01, 0x02],
+                vec![0x05, 0x00, 0x02, 0x90, 0x00],
+            ]),
+            Err(Error::UnexpectedResponse)
+        ));
+
+        // Repeated continuation
+        assert!(matches!(
+            decode_response(&[
+                vec![0x05, 0x00, 0x00, 0x00, 0x06, 0x01, 0x02],
+                vec![0x05, 0x00, 0x01, 0x03, 0x04],
+                vec![0x05, 0x00, 0x01, 0x90, 0x00],
+            ]),
+            Err(Error::UnexpectedResponse)
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        for mtu in [5, 8, 23, 128, 255] {
+            let payload: Vec<u8> = (0..=255).collect();
+
+            // Responses use the command tag for all frames
+            let frames: Vec<_> = encode_frames(0x05, BLE_CMD_CONTINUATION, &payload, mtu)
+                .into_iter()
+                .map(|mut f| {
+                    f[0] = 0x05;
+                    f
+                })
+                .collect();
+
+            assert_eq!(decode_response(&frames).unwrap(), payload, "mtu {mtu}");
+        }
+    }
+}
@@ -14,20 +14,32 @@
 
 use std::{fmt::Debug, time::Duration};
 
-#[cfg(feature = "transport_ble")]
-use tracing::warn;
-
-use tracing::debug;
+use crate::trace::{debug, warn};
 
 #[cfg(feature = "transport_usb")]
 mod usb;
 #[cfg(feature = "transport_usb")]
-pub use usb::{UsbDevice, UsbInfo, UsbTransport};
+pub use usb::{HotplugEvent, UsbDevice, UsbFilters, UsbInfo, UsbTransport, LEDGER_USAGE_PAGE};
 
 #[cfg(feature = "transport_ble")]
 mod ble;
 #[cfg(feature = "transport_ble")]
-pub use ble::{BleDevice, BleInfo, BleTransport};
+pub use ble::{
+    BleAdvState, BleCapabilities, BleConnection, BleDevice, BleFilters, BleInfo, BleSpec,
+    BleTransport, ConnectionPriority, DeviceEvent,
+};
+#[cfg(feature = "transport_ble")]
+pub use btleplug::{api::BDAddr, platform::PeripheralId};
+
+#[cfg(feature = "transport_ble")]
+mod ble_framing;
+#[cfg(feature = "transport_ble")]
+pub use ble_framing::{BLE_CMD_APDU, BLE_CMD_CONTINUATION};
+
+#[cfg(feature = "transport_ble")]
+mod reconnect;
+#[cfg(feature = "transport_ble")]
+pub use reconnect::{KeepAlive, ReconnectPolicy};
 
 #[cfg(feature = "transport_tcp")]
 mod tcp;
@@ -35,10 +47,18 @@ mod tcp;
 pub use tcp::{TcpDevice, TcpInfo, TcpTransport};
 
 use crate::{
-    info::{ConnInfo, LedgerInfo},
-    Error, Exchange, Filters,
+    info::{ConnInfo, ConnType, LedgerInfo},
+    logging::Direction,
+    CancellationToken, Error, Exchange, Filters,
 };
 
+#[cfg(any(feature = "transport_usb", feature = "transport_tcp"))]
+use crate::info::Model;
+
+/// Default maximum accepted response length for transports (configurable per device),
+/// guards against unbounded allocation on corrupt or malicious responses
+pub const DEFAULT_MAX_RESPONSE_LEN: usize = 4096;
+
 /// [Transport] trait provides an abstract interface for transport implementations
 #[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 pub trait Transport {
@@ -54,8 +74,66 @@ pub trait Transport {
 
     /// Connect to a device using info from a previous list operation
     async fn connect(&mut self, info: Self::Info) -> Result<Self::Device, Error>;
+
+    /// Connect to a device with a timeout bounding the whole connection sequence,
+    /// returning [Error::ConnectTimeout] if this elapses
+    async fn connect_timeout(
+        &mut self,
+        info: Self::Info,
+        timeout: Duration,
+    ) -> Result<Self::Device, Error>
+    where
+        Self::Info: Send,
+    {
+        match crate::runtime::timeout(timeout, self.connect(info)).await {
+            Ok(r) => r,
+            Err(_) => Err(Error::ConnectTimeout("connect")),
+        }
+    }
+
+    /// Connect to a device using a [LedgerInfo] from a previous list operation,
+    /// returning [Error::ConnTypeMismatch] if the connection type is not supported
+    /// by this transport
+    async fn connect_by_info(&mut self, info: LedgerInfo) -> Result<Self::Device, Error>
+    where
+        Self::Info: TryFrom<LedgerInfo> + Send,
+        Error: From<<Self::Info as TryFrom<LedgerInfo>>::Error>,
+    {
+        let i = Self::Info::try_from(info)?;
+        self.connect(i).await
+    }
+
+    /// Wait for a device matching the provided filters to become available,
+    /// returning [Error::Timeout] if none appears within `timeout`
+    ///
+    /// By default this polls [Transport::list] (`hidapi` does not provide hotplug
+    /// notifications), transports may override this with event-based discovery.
+    async fn wait_for_device(
+        &mut self,
+        filters: Self::Filters,
+        timeout: Duration,
+    ) -> Result<LedgerInfo, Error>
+    where
+        Self::Filters: Clone + Send,
+    {
+        let poll = async move {
+            loop {
+                let mut devices = self.list(filters.clone()).await?;
+                if !devices.is_empty() {
+                    return Ok(devices.remove(0));
+                }
+
+                crate::runtime::sleep(WAIT_POLL_INTERVAL).await;
+            }
+        };
+
+        crate::runtime::timeout(timeout, poll).await?
+    }
 }
 
+/// Interval between [Transport::list] calls in [Transport::wait_for_device]
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Blanket [Transport] implementation for references types
 #[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl<T: Transport + Send> Transport for &mut T
@@ -74,6 +152,23 @@ where
     async fn connect(&mut self, info: Self::Info) -> Result<Self::Device, Error> {
         <T as Transport>::connect(self, info).await
     }
+    async fn connect_timeout(
+        &mut self,
+        info: Self::Info,
+        timeout: Duration,
+    ) -> Result<Self::Device, Error> {
+        <T as Transport>::connect_timeout(self, info, timeout).await
+    }
+    async fn wait_for_device(
+        &mut self,
+        filters: Self::Filters,
+        timeout: Duration,
+    ) -> Result<LedgerInfo, Error>
+    where
+        Self::Filters: Clone + Send,
+    {
+        <T as Transport>::wait_for_device(self, filters, timeout).await
+    }
 }
 
 /// [GenericTransport] for device communication, abstracts underlying transport types
@@ -87,6 +182,9 @@ pub struct GenericTransport {
 
     #[cfg(feature = "transport_tcp")]
     tcp: TcpTransport,
+
+    /// Devices from the last [Transport::list] call, see [GenericTransport::connect_index]
+    listed: Vec<LedgerInfo>,
 }
 
 /// [GenericDevice] for communication with ledger devices, abstracts underlying transport types
@@ -116,8 +214,17 @@ impl GenericTransport {
 
             #[cfg(feature = "transport_tcp")]
             tcp: TcpTransport::new()?,
+
+            listed: vec![],
         })
     }
+
+    /// Connect to a device by index into the results of the last [Transport::list] call,
+    /// returning [Error::InvalidDeviceIndex] where this is out of range
+    pub async fn connect_index(&mut self, idx: usize) -> Result<GenericDevice, Error> {
+        let info = self.listed.get(idx).ok_or(Error::InvalidDeviceIndex(idx))?;
+        self.connect(info.clone()).await
+    }
 }
 
 #[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
@@ -132,7 +239,7 @@ impl Transport for GenericTransport {
 
         #[cfg(feature = "transport_usb")]
         if filters == Filters::Any || filters == Filters::Hid {
-            let mut d = self.usb.list(()).await?;
+            let mut d = self.usb.list(UsbFilters::default()).await?;
             devices.append(&mut d);
         }
 
@@ -141,7 +248,7 @@ impl Transport for GenericTransport {
             // BLE discovery is allowed to fail if not exclusively selected
             // as dbus does not always provide the relevant service (eg. under WSL)
             // TODO: work out whether we can detect this to separate no BLE from discovery failure
-            match self.ble.list(()).await {
+            match self.ble.list(BleFilters::default()).await {
                 Ok(mut d) => devices.append(&mut d),
                 Err(e) if filters == Filters::Any => {
                     warn!("BLE discovery failed: {e:?}");
@@ -156,6 +263,8 @@ impl Transport for GenericTransport {
             devices.append(&mut d);
         }
 
+        self.listed = devices.clone();
+
         Ok(devices)
     }
 
@@ -175,6 +284,185 @@ impl Transport for GenericTransport {
 
         Ok(d)
     }
+
+    /// Connect to a ledger device with a timeout, using transport-specific
+    /// timeout handling where available
+    async fn connect_timeout(
+        &mut self,
+        info: LedgerInfo,
+        timeout: Duration,
+    ) -> Result<GenericDevice, Error> {
+        debug!("Connecting to device: {:?} (timeout: {timeout:?})", info);
+
+        let d = match info.conn {
+            #[cfg(feature = "transport_usb")]
+            ConnInfo::Usb(i) => self
+                .usb
+                .connect_timeout(i, timeout)
+                .await
+                .map(GenericDevice::Usb)?,
+            #[cfg(feature = "transport_tcp")]
+            ConnInfo::Tcp(i) => self
+                .tcp
+                .connect_timeout(i, timeout)
+                .await
+                .map(GenericDevice::Tcp)?,
+            #[cfg(feature = "transport_ble")]
+            ConnInfo::Ble(i) => self
+                .ble
+                .connect_timeout(i, timeout)
+                .await
+                .map(GenericDevice::Ble)?,
+        };
+
+        Ok(d)
+    }
+}
+
+/// [AnyTransport] wraps a single transport selected at runtime, with devices
+/// returned as [GenericDevice]s
+///
+/// This is useful where the transport is chosen by configuration, while
+/// [GenericTransport] combines all enabled transports.
+/// Filters are not supported, [Transport::list] uses the default filters
+/// for the wrapped transport.
+pub enum AnyTransport {
+    #[cfg(feature = "transport_usb")]
+    Usb(UsbTransport),
+    #[cfg(feature = "transport_tcp")]
+    Tcp(TcpTransport),
+    #[cfg(feature = "transport_ble")]
+    Ble(BleTransport),
+}
+
+impl AnyTransport {
+    /// Fetch the connection type for the wrapped transport
+    pub fn kind(&self) -> ConnType {
+        match self {
+            #[cfg(feature = "transport_usb")]
+            Self::Usb(_) => ConnType::Usb,
+            #[cfg(feature = "transport_tcp")]
+            Self::Tcp(_) => ConnType::Tcp,
+            #[cfg(feature = "transport_ble")]
+            Self::Ble(_) => ConnType::Ble,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+impl Transport for AnyTransport {
+    type Filters = ();
+    type Info = LedgerInfo;
+    type Device = GenericDevice;
+
+    /// List available ledger devices using the wrapped transport
+    async fn list(&mut self, _filters: ()) -> Result<Vec<LedgerInfo>, Error> {
+        match self {
+            #[cfg(feature = "transport_usb")]
+            Self::Usb(t) => t.list(UsbFilters::default()).await,
+            #[cfg(feature = "transport_tcp")]
+            Self::Tcp(t) => t.list(()).await,
+            #[cfg(feature = "transport_ble")]
+            Self::Ble(t) => t.list(BleFilters::default()).await,
+        }
+    }
+
+    /// Connect to a ledger device using the wrapped transport, returning
+    /// [Error::ConnTypeMismatch] if `info` is for a different transport
+    async fn connect(&mut self, info: LedgerInfo) -> Result<GenericDevice, Error> {
+        let d = match self {
+            #[cfg(feature = "transport_usb")]
+            Self::Usb(t) => t.connect(info.try_into()?).await?.into(),
+            #[cfg(feature = "transport_tcp")]
+            Self::Tcp(t) => t.connect(info.try_into()?).await?.into(),
+            #[cfg(feature = "transport_ble")]
+            Self::Ble(t) => t.connect(info.try_into()?).await?.into(),
+        };
+
+        Ok(d)
+    }
+
+    /// Connect to a ledger device with a timeout using the wrapped transport
+    async fn connect_timeout(
+        &mut self,
+        info: LedgerInfo,
+        timeout: Duration,
+    ) -> Result<GenericDevice, Error> {
+        let d = match self {
+            #[cfg(feature = "transport_usb")]
+            Self::Usb(t) => t.connect_timeout(info.try_into()?, timeout).await?.into(),
+            #[cfg(feature = "transport_tcp")]
+            Self::Tcp(t) => t.connect_timeout(info.try_into()?, timeout).await?.into(),
+            #[cfg(feature = "transport_ble")]
+            Self::Ble(t) => t.connect_timeout(info.try_into()?, timeout).await?.into(),
+        };
+
+        Ok(d)
+    }
+}
+
+#[cfg(feature = "transport_usb")]
+impl From<UsbTransport> for AnyTransport {
+    fn from(value: UsbTransport) -> Self {
+        Self::Usb(value)
+    }
+}
+
+#[cfg(feature = "transport_tcp")]
+impl From<TcpTransport> for AnyTransport {
+    fn from(value: TcpTransport) -> Self {
+        Self::Tcp(value)
+    }
+}
+
+#[cfg(feature = "transport_ble")]
+impl From<BleTransport> for AnyTransport {
+    fn from(value: BleTransport) -> Self {
+        Self::Ble(value)
+    }
+}
+
+/// List available ledger devices using all enabled transports
+///
+/// Unlike [GenericTransport::list] each transport is constructed independently,
+/// with initialisation or discovery failures for a given backend (eg. bluetooth
+/// powered off) logged and skipped rather than failing the whole call.
+pub async fn list_all(filters: Filters) -> Result<Vec<LedgerInfo>, Error> {
+    let mut devices = vec![];
+
+    #[cfg(feature = "transport_usb")]
+    if filters == Filters::Any || filters == Filters::Hid {
+        match list_with(UsbTransport::new(), UsbFilters::default()).await {
+            Ok(mut d) => devices.append(&mut d),
+            Err(e) => warn!("USB discovery failed: {e:?}"),
+        }
+    }
+
+    #[cfg(feature = "transport_ble")]
+    if filters == Filters::Any || filters == Filters::Ble {
+        match list_with(BleTransport::new().await, BleFilters::default()).await {
+            Ok(mut d) => devices.append(&mut d),
+            Err(e) => warn!("BLE discovery failed: {e:?}"),
+        }
+    }
+
+    #[cfg(feature = "transport_tcp")]
+    if filters == Filters::Any || filters == Filters::Tcp {
+        match list_with(TcpTransport::new(), ()).await {
+            Ok(mut d) => devices.append(&mut d),
+            Err(e) => warn!("TCP discovery failed: {e:?}"),
+        }
+    }
+
+    Ok(devices)
+}
+
+/// Helper to list devices using a newly constructed transport
+async fn list_with<T: Transport>(
+    t: Result<T, Error>,
+    filters: T::Filters,
+) -> Result<Vec<LedgerInfo>, Error> {
+    t?.list(filters).await
 }
 
 impl GenericDevice {
@@ -190,6 +478,51 @@ impl GenericDevice {
         }
     }
 
+    /// Fetch device information (model and connection info) for a device
+    ///
+    /// This matches the [LedgerInfo] returned by [Transport::list], allowing
+    /// applications to hold a single [GenericDevice] type for any connection.
+    pub fn ledger_info(&self) -> LedgerInfo {
+        let model = match self {
+            #[cfg(feature = "transport_usb")]
+            GenericDevice::Usb(d) => Model::from_pid(d.info.pid),
+            #[cfg(feature = "transport_ble")]
+            GenericDevice::Ble(d) => d.model(),
+            #[cfg(feature = "transport_tcp")]
+            GenericDevice::Tcp(_) => Model::Unknown(0),
+        };
+
+        LedgerInfo {
+            model,
+            label: None,
+            conn: self.info(),
+        }
+    }
+
+    /// Set a callback to be invoked for each APDU exchanged with the device
+    pub fn set_apdu_logger(&mut self, f: impl Fn(Direction, &[u8]) + Send + Sync + 'static) {
+        match self {
+            #[cfg(feature = "transport_usb")]
+            GenericDevice::Usb(d) => d.set_apdu_logger(f),
+            #[cfg(feature = "transport_ble")]
+            GenericDevice::Ble(d) => d.set_apdu_logger(f),
+            #[cfg(feature = "transport_tcp")]
+            GenericDevice::Tcp(d) => d.set_apdu_logger(f),
+        }
+    }
+
+    /// Set a [Metrics](crate::metrics::Metrics) handle to be invoked for each exchange
+    pub fn set_metrics(&mut self, metrics: Option<crate::metrics::MetricsHandle>) {
+        match self {
+            #[cfg(feature = "transport_usb")]
+            GenericDevice::Usb(d) => d.set_metrics(metrics),
+            #[cfg(feature = "transport_ble")]
+            GenericDevice::Ble(d) => d.set_metrics(metrics),
+            #[cfg(feature = "transport_tcp")]
+            GenericDevice::Tcp(d) => d.set_metrics(metrics),
+        }
+    }
+
     pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
         match self {
             #[cfg(feature = "transport_usb")]
@@ -215,6 +548,51 @@ impl Exchange for GenericDevice {
             Self::Tcp(d) => d.exchange(command, timeout).await,
         }
     }
+
+    /// Exchange a sequence of APDUs with the [GenericDevice]
+    async fn exchange_many(
+        &mut self,
+        commands: &[Vec<u8>],
+        timeout: Duration,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        match self {
+            #[cfg(feature = "transport_usb")]
+            Self::Usb(d) => d.exchange_many(commands, timeout).await,
+            #[cfg(feature = "transport_ble")]
+            Self::Ble(d) => d.exchange_many(commands, timeout).await,
+            #[cfg(feature = "transport_tcp")]
+            Self::Tcp(d) => d.exchange_many(commands, timeout).await,
+        }
+    }
+
+    /// Fetch the maximum payload chunk size for the underlying transport
+    fn max_payload_chunk(&self) -> usize {
+        match self {
+            #[cfg(feature = "transport_usb")]
+            Self::Usb(d) => d.max_payload_chunk(),
+            #[cfg(feature = "transport_ble")]
+            Self::Ble(d) => d.max_payload_chunk(),
+            #[cfg(feature = "transport_tcp")]
+            Self::Tcp(d) => d.max_payload_chunk(),
+        }
+    }
+
+    /// Exchange an APDU with the [GenericDevice], supporting cancellation
+    async fn exchange_cancellable(
+        &mut self,
+        command: &[u8],
+        timeout: Duration,
+        cancel: CancellationToken,
+    ) -> Result<Vec<u8>, Error> {
+        match self {
+            #[cfg(feature = "transport_usb")]
+            Self::Usb(d) => d.exchange_cancellable(command, timeout, cancel).await,
+            #[cfg(feature = "transport_ble")]
+            Self::Ble(d) => d.exchange_cancellable(command, timeout, cancel).await,
+            #[cfg(feature = "transport_tcp")]
+            Self::Tcp(d) => d.exchange_cancellable(command, timeout, cancel).await,
+        }
+    }
 }
 
 #[cfg(feature = "transport_usb")]
@@ -237,3 +615,64 @@ impl From<BleDevice> for GenericDevice {
         Self::Ble(value)
     }
 }
+
+#[cfg(all(test, feature = "transport_tcp"))]
+mod tests {
+    use super::*;
+
+    /// Mock transport, reporting a device after `n` list calls
+    struct MockTransport(usize);
+
+    #[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+    impl Transport for MockTransport {
+        type Filters = ();
+        type Info = TcpInfo;
+        type Device = TcpDevice;
+
+        async fn list(&mut self, _filters: ()) -> Result<Vec<LedgerInfo>, Error> {
+            match self.0 {
+                0 => Ok(vec![LedgerInfo {
+                    model: Model::Unknown(0),
+                    label: None,
+                    conn: TcpInfo::default().into(),
+                }]),
+                _ => {
+                    self.0 -= 1;
+                    Ok(vec![])
+                }
+            }
+        }
+
+        async fn connect(&mut self, _info: TcpInfo) -> Result<TcpDevice, Error> {
+            Err(Error::NoDevices)
+        }
+    }
+
+    #[tokio::test]
+    async fn wait_for_device() {
+        let mut t = MockTransport(2);
+        let i = t.wait_for_device((), Duration::from_secs(5)).await.unwrap();
+        assert_eq!(i.conn, TcpInfo::default().into());
+
+        let mut t = MockTransport(usize::MAX);
+        let r = t.wait_for_device((), Duration::from_millis(100)).await;
+        assert!(matches!(r, Err(Error::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn any_transport_connect() {
+        let l = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = l.local_addr().unwrap();
+
+        let mut t = AnyTransport::from(TcpTransport::new().unwrap());
+        assert_eq!(t.kind(), ConnType::Tcp);
+
+        let info = LedgerInfo {
+            model: Model::Unknown(0),
+            label: None,
+            conn: TcpInfo { addr }.into(),
+        };
+        let d = t.connect(info).await.unwrap();
+        assert!(matches!(d, GenericDevice::Tcp(_)));
+    }
+}
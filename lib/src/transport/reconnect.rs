@@ -0,0 +1,279 @@
+//! Automatic reconnection for BLE devices
+//!
+//! [KeepAlive] wraps a [BleDevice], re-establishing the connection with
+//! exponential backoff (see [ReconnectPolicy]) when the device disconnects.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+    time::Duration,
+};
+
+use btleplug::platform::PeripheralId;
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        Mutex,
+    },
+    task::JoinHandle,
+};
+
+use crate::trace::{debug, warn};
+use crate::{info::LedgerInfo, runtime, CancellationToken, Error, Exchange};
+
+use super::{BleDevice, BleTransport, DeviceEvent};
+
+/// Reconnection backoff policy
+///
+/// The delay prior to each attempt doubles from `base` up to `max`, then is
+/// reduced by a random fraction of up to `jitter` to avoid synchronised retries.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReconnectPolicy {
+    /// Delay prior to the first reconnection attempt
+    pub base: Duration,
+    /// Maximum delay between attempts
+    pub max: Duration,
+    /// Maximum fraction of the delay to remove at random (`0.0..=1.0`)
+    pub jitter: f64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Compute the delay prior to the provided (zero-indexed) reconnection attempt
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let scale = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        let d = self.base.saturating_mul(scale).min(self.max);
+
+        let jitter = self.jitter.clamp(0.0, 1.0) * random_unit();
+        d.mul_f64(1.0 - jitter)
+    }
+}
+
+/// Helper to generate a random value in `0.0..1.0` without an additional dependency
+fn random_unit() -> f64 {
+    let v = RandomState::new().build_hasher().finish();
+    (v >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// BLE device wrapper, automatically reconnecting on disconnection
+///
+/// Reconnection runs in a background task owning the [BleTransport], emitting
+/// [DeviceEvent::Disconnected], [DeviceEvent::Reconnecting] for each attempt,
+/// and [DeviceEvent::Connected] on success (see [KeepAlive::subscribe]).
+/// Exchanges while disconnected fail with [Error::Closed].
+pub struct KeepAlive {
+    info: LedgerInfo,
+    device: Arc<Mutex<Option<BleDevice>>>,
+    events: broadcast::Sender<DeviceEvent>,
+    stop: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+/// Reconnection event channel capacity
+const KEEPALIVE_EVENT_CAPACITY: usize = 16;
+
+impl KeepAlive {
+    /// Wrap a connected [BleDevice], using `transport` to reconnect as required
+    pub async fn new(
+        mut transport: BleTransport,
+        device: BleDevice,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, Error> {
+        let info = LedgerInfo {
+            model: device.model(),
+            label: None,
+            conn: device.info.clone().into(),
+        };
+        let id = device.id();
+
+        let rx = transport.subscribe_peripheral_events().await?;
+
+        let device = Arc::new(Mutex::new(Some(device)));
+        let events = broadcast::channel(KEEPALIVE_EVENT_CAPACITY).0;
+        let stop = CancellationToken::new();
+
+        let task = tokio::task::spawn(reconnect_task(
+            transport,
+            rx,
+            id,
+            info.clone(),
+            device.clone(),
+            policy,
+            events.clone(),
+            stop.clone(),
+        ));
+
+        Ok(Self {
+            info,
+            device,
+            events,
+            stop,
+            task,
+        })
+    }
+
+    /// Fetch information for the wrapped device
+    pub fn info(&self) -> &LedgerInfo {
+        &self.info
+    }
+
+    /// Subscribe to connection and reconnection events
+    pub fn subscribe(&self) -> broadcast::Receiver<DeviceEvent> {
+        self.events.subscribe()
+    }
+
+    /// Stop reconnecting, the current connection (if any) is retained
+    pub fn stop(&self) {
+        self.stop.cancel();
+    }
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        self.stop.cancel();
+        self.task.abort();
+    }
+}
+
+/// [Exchange] implementation, forwarding to the connected device
+#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+impl Exchange for KeepAlive {
+    async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        match self.device.lock().await.as_mut() {
+            Some(d) => d.exchange(command, timeout).await,
+            None => Err(Error::Closed),
+        }
+    }
+}
+
+/// Helper to match disconnection events by peripheral identifier
+///
+/// Device addresses are not reported on all platforms (eg. zeroed on macOS / iOS),
+/// so these cannot be used to distinguish devices.
+fn is_disconnect<K: PartialEq>((k, e): &(K, DeviceEvent), id: &K) -> bool {
+    k == id && matches!(e, DeviceEvent::Disconnected(_))
+}
+
+/// Background task, awaiting disconnection then reconnecting with backoff
+#[allow(clippy::too_many_arguments)]
+async fn reconnect_task(
+    mut transport: BleTransport,
+    mut rx: broadcast::Receiver<(PeripheralId, DeviceEvent)>,
+    id: PeripheralId,
+    info: LedgerInfo,
+    device: Arc<Mutex<Option<BleDevice>>>,
+    policy: ReconnectPolicy,
+    events: broadcast::Sender<DeviceEvent>,
+    stop: CancellationToken,
+) {
+    loop {
+        // Await disconnection
+        let e = tokio::select! {
+            _ = stop.cancelled() => return,
+            e = rx.recv() => e,
+        };
+        match e {
+            Ok(e) if is_disconnect(&e, &id) => (),
+            Err(RecvError::Closed) => return,
+            _ => continue,
+        }
+
+        debug!("Device {} disconnected, reconnecting", info.conn);
+        let _ = events.send(DeviceEvent::Disconnected(info.clone()));
+
+        // Drop the stale handle
+        device.lock().await.take();
+
+        // Reconnect with backoff
+        let mut attempt = 0;
+        loop {
+            let delay = policy.delay(attempt);
+            attempt = attempt.saturating_add(1);
+
+            let _ = events.send(DeviceEvent::Reconnecting {
+                info: info.clone(),
+                attempt,
+                delay,
+            });
+
+            let r = tokio::select! {
+                _ = stop.cancelled() => return,
+                r = async {
+                    runtime::sleep(delay).await;
+                    transport.connect_by_id(id.clone()).await
+                } => r,
+            };
+
+            match r {
+                Ok(d) => {
+                    debug!("Reconnected to {} (attempt {attempt})", info.conn);
+                    *device.lock().await = Some(d);
+                    let _ = events.send(DeviceEvent::Connected(info.clone()));
+                    break;
+                }
+                Err(e) => warn!("Reconnection attempt {attempt} failed: {e:?}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use btleplug::api::BDAddr;
+
+    use super::*;
+    use crate::{info::Model, transport::BleInfo};
+
+    #[test]
+    fn backoff_delays() {
+        let p = ReconnectPolicy {
+            base: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            jitter: 0.0,
+        };
+
+        let d: Vec<_> = (0..6).map(|a| p.delay(a).as_millis()).collect();
+        assert_eq!(d, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(p.delay(u32::MAX), Duration::from_secs(1));
+
+        // Jitter reduces delays within the configured fraction
+        let p = ReconnectPolicy { jitter: 0.5, ..p };
+        for a in 0..6 {
+            let d = p.delay(a);
+            let max = ReconnectPolicy {
+                jitter: 0.0,
+                ..p.clone()
+            }
+            .delay(a);
+            assert!(d <= max && d >= max / 2, "{d:?} {max:?}");
+        }
+    }
+
+    #[test]
+    fn disconnect_by_peripheral() {
+        // Devices sharing a zeroed address (as reported on macOS / iOS)
+        let info = |name: &str| LedgerInfo {
+            model: Model::NanoX,
+            label: None,
+            conn: BleInfo::new(name.to_string(), BDAddr::default()).into(),
+        };
+        let (a, b) = (info("Nano X A"), info("Nano X B"));
+
+        assert!(is_disconnect(
+            &(1, DeviceEvent::Disconnected(a.clone())),
+            &1
+        ));
+        assert!(!is_disconnect(&(2, DeviceEvent::Disconnected(b)), &1));
+        assert!(!is_disconnect(&(1, DeviceEvent::Connected(a)), &1));
+    }
+}
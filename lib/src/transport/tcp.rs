@@ -1,34 +1,80 @@
 use std::{
+    collections::HashMap,
     fmt::Display,
+    io::ErrorKind,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
+use crate::trace::{debug, error, warn};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt, Interest},
-    net::{TcpListener, TcpStream},
+    net::{TcpListener, TcpSocket, TcpStream},
 };
-use tracing::{debug, error};
 
 use crate::{
-    info::{LedgerInfo, Model},
-    Error,
+    info::{ConnType, LedgerInfo, Model},
+    logging::{log_apdu, ApduLogger, Direction, Payload},
+    metrics::{with_metrics, MetricsHandle},
+    CancellationToken, Error,
 };
 
-use super::{Exchange, Transport};
+use super::{Exchange, Transport, DEFAULT_MAX_RESPONSE_LEN};
 
 /// TCP transport implementation for interacting with Speculos via the TCP APDU socket
-#[derive(Default)]
-pub struct TcpTransport {}
+///
+/// Where pooling is enabled (see [TcpTransport::set_pooling]) connections are returned
+/// to the transport when a [TcpDevice] is dropped, and reused by subsequent
+/// [Transport::connect] calls to the same address while still alive.
+pub struct TcpTransport {
+    keepalive: bool,
+    reconnect: bool,
+    pooling: bool,
+    pool: TcpPool,
+    /// Devices from the last [Transport::list] call, see [TcpTransport::connect_index]
+    listed: Vec<LedgerInfo>,
+}
+
+/// Idle connections, keyed by address
+type TcpPool = Arc<Mutex<HashMap<SocketAddr, TcpStream>>>;
+
+impl Default for TcpTransport {
+    fn default() -> Self {
+        Self {
+            keepalive: false,
+            reconnect: true,
+            pooling: false,
+            pool: Arc::new(Mutex::new(HashMap::new())),
+            listed: vec![],
+        }
+    }
+}
 
 /// TCP based device
+///
+/// If the connection is dropped (for example when Speculos is restarted) a single
+/// reconnect to the original [TcpInfo] address is attempted prior to failing an exchange,
+/// see [TcpDevice::set_reconnect] to disable this. Requests are only re-sent where the
+/// connection was lost before the request was written, so a command is never executed
+/// twice; connections dropped while awaiting a response fail with [Error::Closed].
 pub struct TcpDevice {
-    s: TcpStream,
+    /// Connection stream, only `None` while being returned to the pool on drop
+    s: Option<TcpStream>,
     pub info: TcpInfo,
+    logger: Option<ApduLogger>,
+    metrics: Option<MetricsHandle>,
+    keepalive: bool,
+    reconnect: bool,
+    pool: Option<TcpPool>,
+    max_response_len: usize,
+    /// Set where an exchange was abandoned with a response pending, requiring
+    /// a new connection prior to the next exchange
+    desynced: bool,
 }
 
 /// TCP device information
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct TcpInfo {
     pub addr: SocketAddr,
 }
@@ -50,8 +96,66 @@ impl Display for TcpInfo {
 impl TcpTransport {
     /// Create a new [TcpTransport] instance
     pub fn new() -> Result<Self, Error> {
-        Ok(Self {})
+        Ok(Self::default())
+    }
+
+    /// Connect to a device by index into the results of the last [Transport::list] call,
+    /// returning [Error::InvalidDeviceIndex] where this is out of range
+    pub async fn connect_index(&mut self, idx: usize) -> Result<TcpDevice, Error> {
+        let info = self.listed.get(idx).ok_or(Error::InvalidDeviceIndex(idx))?;
+        let info = TcpInfo::try_from(info.clone())?;
+        self.connect(info).await
+    }
+
+    /// Enable or disable TCP keepalive for new connections
+    pub fn set_keepalive(&mut self, enabled: bool) {
+        self.keepalive = enabled;
+    }
+
+    /// Enable or disable transparent reconnection for new connections (enabled by default,
+    /// requests are only re-sent where the connection was dropped prior to writing)
+    pub fn set_reconnect(&mut self, enabled: bool) {
+        self.reconnect = enabled;
     }
+
+    /// Enable or disable connection pooling (disabled by default)
+    ///
+    /// Disabling pooling does not evict existing idle connections, see [TcpTransport::close].
+    pub fn set_pooling(&mut self, enabled: bool) {
+        self.pooling = enabled;
+    }
+
+    /// Close an idle pooled connection to the provided address,
+    /// returning `true` if a connection was evicted
+    pub fn close(&mut self, addr: SocketAddr) -> bool {
+        self.pool.lock().unwrap().remove(&addr).is_some()
+    }
+}
+
+/// Helper to check whether an idle connection is still alive and has no pending data
+fn is_alive(s: &TcpStream) -> bool {
+    match s.try_read(&mut [0u8; 1]) {
+        // Would block, connection is open and idle
+        Err(e) if e.kind() == ErrorKind::WouldBlock => true,
+        // Closed, errored, or unexpected data pending
+        _ => false,
+    }
+}
+
+/// Helper to open a TCP connection, optionally enabling keepalive
+async fn connect_stream(addr: SocketAddr, keepalive: bool) -> Result<TcpStream, Error> {
+    let s = match addr {
+        SocketAddr::V4(_) => TcpSocket::new_v4()?,
+        SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+
+    if keepalive {
+        s.set_keepalive(true)?;
+    }
+
+    let s = s.connect(addr).await?;
+
+    Ok(s)
 }
 
 #[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
@@ -79,10 +183,13 @@ impl Transport for TcpTransport {
                 devices.push(LedgerInfo {
                     conn: TcpInfo { addr }.into(),
                     model: Model::Unknown(0),
+                    label: None,
                 });
             }
         }
 
+        self.listed = devices.clone();
+
         Ok(devices)
     }
 
@@ -90,21 +197,69 @@ impl Transport for TcpTransport {
     async fn connect(&mut self, info: TcpInfo) -> Result<TcpDevice, Error> {
         debug!("Connecting to: {:?}", info);
 
-        // Connect to provided TCP socket
-        let s = match TcpStream::connect(info.addr).await {
-            Ok(v) => v,
-            Err(e) => {
-                error!("TCP connection failed: {:?}", e);
-                return Err(e.into());
+        // Reuse pooled connection if available
+        let pooled = match self.pooling {
+            true => self.pool.lock().unwrap().remove(&info.addr),
+            false => None,
+        };
+
+        let s = match pooled {
+            Some(s) if is_alive(&s) => {
+                debug!("Reusing pooled connection to: {}", info.addr);
+                s
             }
+            // Otherwise connect to provided TCP socket
+            _ => match connect_stream(info.addr, self.keepalive).await {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("TCP connection failed: {:?}", e);
+                    return Err(e);
+                }
+            },
         };
 
         // Return TCP device handle
-        Ok(TcpDevice { s, info })
+        Ok(TcpDevice {
+            s: Some(s),
+            info,
+            logger: None,
+            metrics: None,
+            keepalive: self.keepalive,
+            reconnect: self.reconnect,
+            pool: self.pooling.then(|| self.pool.clone()),
+            max_response_len: DEFAULT_MAX_RESPONSE_LEN,
+            desynced: false,
+        })
     }
 }
 
 impl TcpDevice {
+    /// Set a callback to be invoked for each APDU exchanged with the device
+    pub fn set_apdu_logger(&mut self, f: impl Fn(Direction, &[u8]) + Send + Sync + 'static) {
+        self.logger = Some(std::sync::Arc::new(f));
+    }
+
+    /// Set a [Metrics](crate::metrics::Metrics) handle to be invoked for each exchange
+    pub fn set_metrics(&mut self, metrics: Option<MetricsHandle>) {
+        self.metrics = metrics;
+    }
+
+    /// Set the maximum accepted response length (defaults to [DEFAULT_MAX_RESPONSE_LEN]),
+    /// responses declaring a larger length fail with [Error::ResponseTooLarge]
+    pub fn set_max_response_len(&mut self, len: usize) {
+        self.max_response_len = len;
+    }
+
+    /// Enable or disable transparent reconnection where the connection has been dropped
+    pub fn set_reconnect(&mut self, enabled: bool) {
+        self.reconnect = enabled;
+    }
+
+    /// Internal helper to fetch the connection stream
+    fn stream(&mut self) -> &mut TcpStream {
+        self.s.as_mut().expect("stream only taken on drop")
+    }
+
     /// Internal helper to write command data
     async fn write_command(&mut self, req: &[u8]) -> Result<(), Error> {
         // Setup data buffer to send
@@ -116,10 +271,10 @@ impl TcpDevice {
         // Write APDU data
         buff[4..].copy_from_slice(req);
 
-        debug!("TX: {:02x?}", buff);
+        debug!("TX: {:?}", Payload::full(&buff));
 
         // Send APDU request
-        if let Err(e) = self.s.write_all(&buff).await {
+        if let Err(e) = self.stream().write_all(&buff).await {
             error!("Failed to write request APDU: {:?}", e);
             return Err(e.into());
         }
@@ -132,7 +287,7 @@ impl TcpDevice {
         let mut buff = vec![0u8; 4];
 
         // Read response length (u32 big endian + 2 bytes for status)
-        let n = match self.s.read_exact(&mut buff[..4]).await {
+        let n = match self.stream().read_exact(&mut buff[..4]).await {
             Ok(_) => u32::from_be_bytes(buff[..4].try_into().unwrap()) as usize + 2,
             Err(e) => {
                 error!("Failed to read response APDU length: {:?}", e);
@@ -140,21 +295,72 @@ impl TcpDevice {
             }
         };
 
+        // Check length is reasonable prior to allocation
+        if n > self.max_response_len {
+            error!("Response length {n} exceeds maximum");
+            return Err(Error::ResponseTooLarge {
+                len: n,
+                max: self.max_response_len,
+            });
+        }
+
         // Read response data
         buff.resize(n + 4, 0);
-        if let Err(e) = self.s.read_exact(&mut buff[4..][..n]).await {
+        if let Err(e) = self.stream().read_exact(&mut buff[4..][..n]).await {
             error!("Failed to read response APDU data: {:?}", e);
             return Err(e.into());
         }
 
-        debug!("RX: {:02x?}", buff);
+        debug!("RX: {:?}", Payload::full(&buff));
 
         // Return response data
         Ok(buff[4..].to_vec())
     }
 
+    /// Internal helper to re-establish a dropped or desynchronised connection
+    async fn reconnect(&mut self) -> Result<(), Error> {
+        warn!("Reconnecting TCP connection to {}", self.info);
+
+        self.s = Some(connect_stream(self.info.addr, self.keepalive).await?);
+
+        Ok(())
+    }
+
+    /// Internal helper to write a request and await the response
+    ///
+    /// Where reconnection is enabled, a connection found dead prior to or while writing
+    /// the request is re-established and the request re-sent. Once the request has been
+    /// written it may have been executed, so later failures are returned to the caller
+    /// rather than risking a duplicate command.
+    async fn exchange_inner(&mut self, req: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        // Replace connections found dropped prior to writing, or left with
+        // a pending response by an abandoned exchange
+        if self.desynced || (self.reconnect && !is_alive(self.stream())) {
+            self.reconnect().await?;
+            self.desynced = false;
+        }
+
+        // Write APDU request, retrying once on failure
+        match self.write_command(req).await {
+            Err(Error::Closed) if self.reconnect => {
+                self.reconnect().await?;
+                self.write_command(req).await?;
+            }
+            r => r?,
+        }
+
+        // Await APDU response with timeout, the response may still arrive
+        // on this connection so a timeout leaves the connection desynchronised
+        let r = crate::runtime::timeout(timeout, self.read_data()).await;
+        if let Err(Error::Timeout) = r {
+            self.desynced = true;
+        }
+        r?
+    }
+
     pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
-        let r = self.s.ready(Interest::WRITABLE).await?;
+        let s = self.s.as_ref().ok_or(Error::Closed)?;
+        let r = s.ready(Interest::WRITABLE).await?;
         Ok(!r.is_read_closed() || !r.is_write_closed())
     }
 }
@@ -163,17 +369,295 @@ impl TcpDevice {
 #[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Exchange for TcpDevice {
     async fn exchange(&mut self, req: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
-        // Write APDU request
-        self.write_command(req).await?;
-
-        // Await APDU response with timeout
-        let d = match tokio::time::timeout(timeout, self.read_data()).await {
-            Ok(Ok(d)) => d,
-            Ok(Err(e)) => return Err(e),
-            Err(e) => return Err(e.into()),
+        let metrics = self.metrics.clone();
+
+        with_metrics(&metrics, ConnType::Tcp, req, async {
+            log_apdu(&self.logger, Direction::Tx, req);
+
+            // Exchange APDU, reconnecting if the connection has been dropped
+            let d = self.exchange_inner(req, timeout).await?;
+
+            log_apdu(&self.logger, Direction::Rx, &d);
+
+            // Return response data
+            Ok(d)
+        })
+        .await
+    }
+
+    /// Cancelled exchanges leave the response pending on the connection,
+    /// so this is replaced prior to the next exchange
+    async fn exchange_cancellable(
+        &mut self,
+        command: &[u8],
+        timeout: Duration,
+        cancel: CancellationToken,
+    ) -> Result<Vec<u8>, Error> {
+        let r = tokio::select! {
+            r = self.exchange(command, timeout) => r,
+            _ = cancel.cancelled() => Err(Error::Cancelled),
         };
 
-        // Return response data
-        Ok(d)
+        if let Err(Error::Cancelled) = r {
+            self.desynced = true;
+        }
+
+        r
+    }
+}
+
+/// Return connections to the transport pool on drop, where enabled
+impl Drop for TcpDevice {
+    fn drop(&mut self) {
+        if self.desynced {
+            return;
+        }
+        if let (Some(pool), Some(s)) = (&self.pool, self.s.take()) {
+            pool.lock().unwrap().insert(self.info.addr, s);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reconnect_on_disconnect() {
+        let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = l.local_addr().unwrap();
+
+        // Mock server, drops the first connection then responds on the second
+        let server = tokio::spawn(async move {
+            let (s, _) = l.accept().await.unwrap();
+            drop(s);
+
+            let (mut s, _) = l.accept().await.unwrap();
+            let mut buff = [0u8; 9];
+            s.read_exact(&mut buff).await.unwrap();
+            s.write_all(&[0x00, 0x00, 0x00, 0x00, 0x90, 0x00])
+                .await
+                .unwrap();
+        });
+
+        let mut t = TcpTransport::new().unwrap();
+        t.set_keepalive(true);
+
+        let mut d = t.connect(TcpInfo { addr }).await.unwrap();
+
+        // Wait for the server to drop the first connection
+        d.stream().readable().await.unwrap();
+
+        let r = d
+            .exchange(&[0xe0, 0x01, 0x00, 0x00, 0x00], Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(r, vec![0x90, 0x00]);
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn no_resend_after_write() {
+        let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = l.local_addr().unwrap();
+
+        // Mock server, accepts the command then drops the connection,
+        // counting commands received over a short window
+        let server = tokio::spawn(async move {
+            let (mut s, _) = l.accept().await.unwrap();
+            let mut buff = [0u8; 9];
+            s.read_exact(&mut buff).await.unwrap();
+            drop(s);
+
+            let mut received = 1;
+            while let Ok(Ok((mut s, _))) =
+                tokio::time::timeout(Duration::from_millis(200), l.accept()).await
+            {
+                if s.read_exact(&mut buff).await.is_ok() {
+                    received += 1;
+                }
+            }
+            received
+        });
+
+        let mut t = TcpTransport::new().unwrap();
+        t.set_reconnect(true);
+
+        let mut d = t.connect(TcpInfo { addr }).await.unwrap();
+
+        let r = d
+            .exchange(&[0xe0, 0x01, 0x00, 0x00, 0x00], Duration::from_secs(1))
+            .await;
+        assert!(matches!(r, Err(Error::Closed)));
+
+        assert_eq!(server.await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn cancel_discards_response() {
+        let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = l.local_addr().unwrap();
+
+        // Mock server, delaying the response to the first command then
+        // responding immediately to the next
+        let server = tokio::spawn(async move {
+            let (mut s, _) = l.accept().await.unwrap();
+            let mut buff = [0u8; 9];
+            s.read_exact(&mut buff).await.unwrap();
+
+            let (mut s2, _) = l.accept().await.unwrap();
+            let _ = s.write_all(&[0x00, 0x00, 0x00, 0x00, 0x69, 0x85]).await;
+
+            s2.read_exact(&mut buff).await.unwrap();
+            s2.write_all(&[0x00, 0x00, 0x00, 0x00, 0x90, 0x00])
+                .await
+                .unwrap();
+        });
+
+        let mut t = TcpTransport::new().unwrap();
+        let mut d = t.connect(TcpInfo { addr }).await.unwrap();
+
+        let cancel = CancellationToken::new();
+        let c = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            c.cancel();
+        });
+
+        let r = d
+            .exchange_cancellable(
+                &[0xe0, 0x01, 0x00, 0x00, 0x00],
+                Duration::from_secs(1),
+                cancel,
+            )
+            .await;
+        assert!(matches!(r, Err(Error::Cancelled)));
+
+        // Following exchange receives its own response
+        let r = d
+            .exchange(&[0xe0, 0x01, 0x00, 0x00, 0x00], Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(r, vec![0x90, 0x00]);
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn no_reconnect() {
+        let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = l.local_addr().unwrap();
+
+        // Mock server, drops the first connection
+        let server = tokio::spawn(async move {
+            let (s, _) = l.accept().await.unwrap();
+            drop(s);
+        });
+
+        let mut t = TcpTransport::new().unwrap();
+        t.set_reconnect(false);
+
+        let mut d = t.connect(TcpInfo { addr }).await.unwrap();
+        server.await.unwrap();
+
+        let r = d
+            .exchange(&[0xe0, 0x01, 0x00, 0x00, 0x00], Duration::from_secs(1))
+            .await;
+        assert!(matches!(r, Err(Error::Closed)));
+    }
+
+    #[tokio::test]
+    async fn pooled_connections() {
+        let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = l.local_addr().unwrap();
+
+        // Mock server, accepting a single connection and responding to two requests
+        let server = tokio::spawn(async move {
+            let (mut s, _) = l.accept().await.unwrap();
+            for _ in 0..2 {
+                let mut buff = [0u8; 9];
+                s.read_exact(&mut buff).await.unwrap();
+                s.write_all(&[0x00, 0x00, 0x00, 0x00, 0x90, 0x00])
+                    .await
+                    .unwrap();
+            }
+            l
+        });
+
+        let mut t = TcpTransport::new().unwrap();
+        t.set_pooling(true);
+
+        // Second connection reuses the pooled socket
+        for _ in 0..2 {
+            let mut d = t.connect(TcpInfo { addr }).await.unwrap();
+            let r = d
+                .exchange(&[0xe0, 0x01, 0x00, 0x00, 0x00], Duration::from_secs(1))
+                .await
+                .unwrap();
+            assert_eq!(r, vec![0x90, 0x00]);
+        }
+
+        let _l = server.await.unwrap();
+
+        assert!(t.close(addr));
+        assert!(!t.close(addr));
+    }
+
+    #[tokio::test]
+    async fn response_too_large() {
+        let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = l.local_addr().unwrap();
+
+        // Mock server, declaring an oversized response
+        let server = tokio::spawn(async move {
+            let (mut s, _) = l.accept().await.unwrap();
+            let mut buff = [0u8; 9];
+            s.read_exact(&mut buff).await.unwrap();
+            s.write_all(&[0xff, 0xff, 0xff, 0xff]).await.unwrap();
+            s
+        });
+
+        let mut t = TcpTransport::new().unwrap();
+        let mut d = t.connect(TcpInfo { addr }).await.unwrap();
+
+        let r = d
+            .exchange(&[0xe0, 0x01, 0x00, 0x00, 0x00], Duration::from_secs(1))
+            .await;
+        assert!(matches!(
+            r,
+            Err(Error::ResponseTooLarge {
+                max: DEFAULT_MAX_RESPONSE_LEN,
+                ..
+            })
+        ));
+
+        let _s = server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn connect_index() {
+        let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = l.local_addr().unwrap();
+
+        let mut t = TcpTransport::new().unwrap();
+        assert!(matches!(
+            t.connect_index(0).await,
+            Err(Error::InvalidDeviceIndex(0))
+        ));
+
+        // Connect using a (simulated) listed device
+        t.listed = vec![LedgerInfo {
+            model: Model::Unknown(0),
+            label: None,
+            conn: TcpInfo { addr }.into(),
+        }];
+        let d = t.connect_index(0).await.unwrap();
+        assert_eq!(d.info.addr, addr);
+
+        assert!(matches!(
+            t.connect_index(1).await,
+            Err(Error::InvalidDeviceIndex(1))
+        ));
     }
 }
@@ -6,20 +6,30 @@
 //! more details.
 //!
 
-use std::{ffi::CString, fmt::Display, io::ErrorKind, time::Duration};
+use std::{
+    collections::VecDeque,
+    ffi::CString,
+    fmt::Display,
+    io::ErrorKind,
+    time::{Duration, Instant},
+};
 
-use hidapi::{HidApi, HidDevice, HidError};
-use tracing::{debug, error, trace, warn};
+use crate::trace::{debug, error, trace, warn};
+use futures::Stream;
+use hidapi::{DeviceInfo, HidApi, HidDevice, HidError};
 
 use crate::{
-    info::{LedgerInfo, Model},
-    Error,
+    check_response,
+    info::{ConnType, LedgerInfo, Model},
+    logging::{log_apdu, ApduLogger, Direction, Payload},
+    metrics::{with_metrics, MetricsHandle},
+    CancellationToken, Error,
 };
 
-use super::{Exchange, Transport};
+use super::{Exchange, Transport, DEFAULT_MAX_RESPONSE_LEN};
 
 /// Basic USB device information
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 #[cfg_attr(feature = "clap", derive(clap::Parser))]
 pub struct UsbInfo {
     #[cfg_attr(feature = "clap", clap(long, value_parser=u16_parse_hex))]
@@ -33,11 +43,21 @@ pub struct UsbInfo {
     #[cfg_attr(feature = "clap", clap(long))]
     /// Device path
     pub path: Option<String>,
+
+    #[cfg_attr(feature = "clap", clap(long))]
+    /// Device serial number (stable across reconnection, where available)
+    pub serial: Option<String>,
 }
 
 impl Display for UsbInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:04x}:{:04x}", self.vid, self.pid)
+        write!(f, "{:04x}:{:04x}", self.vid, self.pid)?;
+
+        if let Some(s) = &self.serial {
+            write!(f, " ({s})")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -54,26 +74,199 @@ fn u16_parse_hex(s: &str) -> Result<u16, std::num::ParseIntError> {
 /// If you don't need low-level control see [crate::LedgerProvider] for a tokio based wrapper.
 pub struct UsbTransport {
     hid_api: HidApi,
+    /// Devices from the last [Transport::list] call, see [UsbTransport::connect_index]
+    listed: Vec<LedgerInfo>,
 }
 
 /// USB HID based device
 pub struct UsbDevice {
     pub info: UsbInfo,
     device: HidDevice,
+    logger: Option<ApduLogger>,
+    metrics: Option<MetricsHandle>,
+    max_response_len: usize,
 }
 
 /// Ledger USB VID
 pub const LEDGER_VID: u16 = 0x2c97;
 
+/// HID usage page for the Ledger generic (APDU) interface
+pub const LEDGER_USAGE_PAGE: u16 = 0xffa0;
+
+/// USB device filters, used when listing devices via [UsbTransport]
+///
+/// By default this matches any device with the Ledger vendor ID ([LEDGER_VID]),
+/// override this to enumerate development or custom devices.
+///
+/// Ledger devices are composite and may expose multiple HID interfaces (for example
+/// the generic APDU interface alongside U2F / FIDO), each of which is enumerated
+/// separately by `hidapi`. Simply grabbing the first interface for a VID / PID pair
+/// may select the U2F interface, which will not respond to APDUs, so by default only
+/// the interface with the Ledger generic usage page ([LEDGER_USAGE_PAGE]) is matched.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UsbFilters {
+    /// Vendor ID to match (or `None` to match any vendor)
+    pub vendor_id: Option<u16>,
+    /// Product IDs to match (or `None` to match any product)
+    pub product_ids: Option<Vec<u16>>,
+    /// HID usage page to match (or `None` to match any interface)
+    ///
+    /// Where the `hidapi` backend does not report usage pages (e.g. `libusb`)
+    /// this falls back to matching interface `0`.
+    pub usage_page: Option<u16>,
+    /// Explicit interface number to match, overriding `usage_page` where set
+    pub interface: Option<i32>,
+}
+
+impl Default for UsbFilters {
+    fn default() -> Self {
+        Self {
+            vendor_id: Some(LEDGER_VID),
+            product_ids: None,
+            usage_page: Some(LEDGER_USAGE_PAGE),
+            interface: None,
+        }
+    }
+}
+
+impl UsbFilters {
+    /// Check whether a VID / PID pair matches the filter
+    pub fn matches(&self, vid: u16, pid: u16) -> bool {
+        if let Some(v) = self.vendor_id {
+            if v != vid {
+                return false;
+            }
+        }
+
+        if let Some(p) = &self.product_ids {
+            if !p.contains(&pid) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Check whether a HID interface (usage page / interface number) matches the filter
+    pub fn matches_interface(&self, usage_page: u16, interface: i32) -> bool {
+        // Explicit interface overrides usage page matching
+        if let Some(i) = self.interface {
+            return i == interface;
+        }
+
+        match self.usage_page {
+            // Usage pages are not reported by all backends, fallback to the first interface
+            Some(_) if usage_page == 0 => interface == 0,
+            Some(p) => p == usage_page,
+            None => true,
+        }
+    }
+
+    /// Check whether a `hidapi` device entry matches the filter
+    fn matches_device(&self, d: &DeviceInfo) -> bool {
+        self.matches(d.vendor_id(), d.product_id())
+            && self.matches_interface(usage_page(d), d.interface_number())
+    }
+}
+
+/// Fetch the HID usage page for a device, `0` where this is not supported by the backend
+#[cfg(not(any(
+    all(target_os = "linux", feature = "transport_usb_libusb"),
+    target_os = "freebsd",
+    target_os = "openbsd"
+)))]
+fn usage_page(d: &DeviceInfo) -> u16 {
+    d.usage_page()
+}
+
+/// Fetch the HID usage page for a device, `0` where this is not supported by the backend
+#[cfg(any(
+    all(target_os = "linux", feature = "transport_usb_libusb"),
+    target_os = "freebsd",
+    target_os = "openbsd"
+))]
+fn usage_page(_d: &DeviceInfo) -> u16 {
+    0
+}
+
+/// USB hotplug event, see [UsbTransport::hotplug]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum HotplugEvent {
+    /// Device connected
+    Arrived,
+    /// Device disconnected
+    Left,
+}
+
+/// Interval between device list refreshes for [UsbTransport::hotplug]
+const HOTPLUG_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 impl UsbTransport {
     /// Create a new [UsbTransport]
     pub fn new() -> Result<Self, Error> {
         Ok(Self {
             hid_api: HidApi::new()?,
+            listed: vec![],
+        })
+    }
+
+    /// Connect to a device by index into the results of the last [Transport::list] call,
+    /// returning [Error::InvalidDeviceIndex] where this is out of range
+    pub async fn connect_index(&mut self, idx: usize) -> Result<UsbDevice, Error> {
+        let info = self.listed.get(idx).ok_or(Error::InvalidDeviceIndex(idx))?;
+        let info = UsbInfo::try_from(info.clone())?;
+        self.connect(info).await
+    }
+
+    /// Watch for devices matching the provided filters being connected or disconnected
+    ///
+    /// Devices present when this is called are reported as [HotplugEvent::Arrived].
+    /// `hidapi` does not support hotplug callbacks so this polls the device list,
+    /// with events delivered within ~500ms of a change.
+    pub fn hotplug(
+        &mut self,
+        filters: UsbFilters,
+    ) -> impl Stream<Item = (HotplugEvent, LedgerInfo)> + '_ {
+        let state = (self, filters, vec![], VecDeque::new());
+
+        futures::stream::unfold(state, |(t, filters, mut known, mut pending)| async move {
+            loop {
+                // Return pending events
+                if let Some(e) = pending.pop_front() {
+                    return Some((e, (t, filters, known, pending)));
+                }
+
+                // Refresh device list and compute changes
+                match t.list(filters.clone()).await {
+                    Ok(devices) => {
+                        pending.extend(hotplug_diff(&known, &devices));
+                        known = devices;
+                    }
+                    Err(e) => warn!("Failed to list USB devices: {e:?}"),
+                }
+
+                if pending.is_empty() {
+                    crate::runtime::sleep(HOTPLUG_POLL_INTERVAL).await;
+                }
+            }
         })
     }
 }
 
+/// Helper to compute hotplug events between two device lists
+fn hotplug_diff(prev: &[LedgerInfo], next: &[LedgerInfo]) -> Vec<(HotplugEvent, LedgerInfo)> {
+    let left = prev
+        .iter()
+        .filter(|i| !next.contains(i))
+        .map(|i| (HotplugEvent::Left, i.clone()));
+    let arrived = next
+        .iter()
+        .filter(|i| !prev.contains(i))
+        .map(|i| (HotplugEvent::Arrived, i.clone()));
+
+    left.chain(arrived).collect()
+}
+
 // With the unstable_async_trait feature we can (correctly) mark this as non-send
 // however [async_trait] can't easily differentiate between send and non-send so we're
 // exposing this as Send for the moment
@@ -94,13 +287,13 @@ unsafe impl Send for UsbTransport {}
 
 #[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Transport for UsbTransport {
-    type Filters = ();
+    type Filters = UsbFilters;
     type Info = UsbInfo;
     type Device = UsbDevice;
 
     /// List available devices using the [UsbTransport]
-    async fn list(&mut self, _filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
-        debug!("Listing USB devices");
+    async fn list(&mut self, filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
+        debug!("Listing USB devices (filters: {filters:?})");
 
         // Refresh available devices
         // TODO: determine whether the refresh call is critical (or, useful?)
@@ -108,19 +301,21 @@ impl Transport for UsbTransport {
             warn!("Failed to refresh devices: {e:?}");
         }
 
-        tokio::time::sleep(Duration::from_millis(200)).await;
+        crate::runtime::sleep(Duration::from_millis(200)).await;
 
         // Fetch list of devices, filtering for ledgers
         let devices: Vec<_> = self
             .hid_api
             .device_list()
-            .filter(|d| d.vendor_id() == LEDGER_VID)
+            .filter(|d| filters.matches_device(d))
             .map(|d| LedgerInfo {
                 model: Model::from_pid(d.product_id()),
+                label: None,
                 conn: UsbInfo {
                     vid: d.vendor_id(),
                     pid: d.product_id(),
                     path: Some(d.path().to_string_lossy().to_string()),
+                    serial: d.serial_number().map(|s| s.to_string()),
                 }
                 .into(),
             })
@@ -128,6 +323,8 @@ impl Transport for UsbTransport {
 
         debug!("devices: {:?}", devices);
 
+        self.listed = devices.clone();
+
         Ok(devices)
     }
 
@@ -140,15 +337,34 @@ impl Transport for UsbTransport {
             let p = CString::new(p.clone()).unwrap();
             self.hid_api.open_path(&p)
 
-        // Otherwise, fallback to (non unique!) vid:pid
+        // Or the serial number, if available
+        } else if let Some(s) = &info.serial {
+            self.hid_api.open_serial(info.vid, info.pid, s)
+
+        // Otherwise, fallback to (non unique!) vid:pid, selecting the generic HID interface
         } else {
-            self.hid_api.open(info.vid, info.pid)
+            let f = UsbFilters {
+                vendor_id: Some(info.vid),
+                product_ids: Some(vec![info.pid]),
+                ..Default::default()
+            };
+
+            match self.hid_api.device_list().find(|d| f.matches_device(d)) {
+                Some(d) => d.open_device(&self.hid_api),
+                None => self.hid_api.open(info.vid, info.pid),
+            }
         };
 
         match d {
             Ok(d) => {
                 debug!("Connected to USB device: {:?}", info);
-                Ok(UsbDevice { device: d, info })
+                Ok(UsbDevice {
+                    device: d,
+                    info,
+                    logger: None,
+                    metrics: None,
+                    max_response_len: DEFAULT_MAX_RESPONSE_LEN,
+                })
             }
             Err(e) => {
                 debug!("Failed to connect to USB device: {:?}", e);
@@ -165,6 +381,22 @@ const HID_PACKET_LEN: usize = 64;
 const HID_HEADER_LEN: usize = 5;
 
 impl UsbDevice {
+    /// Set a callback to be invoked for each APDU exchanged with the device
+    pub fn set_apdu_logger(&mut self, f: impl Fn(Direction, &[u8]) + Send + Sync + 'static) {
+        self.logger = Some(std::sync::Arc::new(f));
+    }
+
+    /// Set a [Metrics](crate::metrics::Metrics) handle to be invoked for each exchange
+    pub fn set_metrics(&mut self, metrics: Option<MetricsHandle>) {
+        self.metrics = metrics;
+    }
+
+    /// Set the maximum accepted response length (defaults to [DEFAULT_MAX_RESPONSE_LEN]),
+    /// responses declaring a larger length fail with [Error::ResponseTooLarge]
+    pub fn set_max_response_len(&mut self, len: usize) {
+        self.max_response_len = len;
+    }
+
     /// Write an APDU to the device
     pub fn write(&mut self, apdu: &[u8]) -> Result<(), Error> {
         debug!("Write APDU");
@@ -174,7 +406,7 @@ impl UsbDevice {
         data.extend_from_slice(&(apdu.len() as u16).to_be_bytes());
         data.extend_from_slice(apdu);
 
-        debug!("TX: {:02x?}", data);
+        debug!("TX: {:?}", Payload::full(&data));
 
         // Write data in 64 byte chunks
         for (i, c) in data.chunks(HID_PACKET_LEN - HID_HEADER_LEN).enumerate() {
@@ -192,7 +424,7 @@ impl UsbDevice {
             // Remaining data
             packet.extend_from_slice(c);
 
-            trace!("Write: 0x{:02x?}", packet);
+            trace!("Write: {:?}", Payload::full(&packet));
 
             // Write HID packet
             self.device.write(&packet)?;
@@ -202,103 +434,296 @@ impl UsbDevice {
     }
 
     /// Read an APDU from the device
+    ///
+    /// `timeout` bounds the complete response, a device that stops sending
+    /// part way through a response results in [Error::Timeout].
     pub fn read(&mut self, timeout: Duration) -> Result<Vec<u8>, Error> {
         debug!("Read APDU");
 
-        let mut buff = [0u8; HID_PACKET_LEN + 1];
-
-        // Read first chunk of response
-        // Timeout argument applied here as once the reply has started timeout bounds should be more consistent
-        let n = match self
-            .device
-            .read_timeout(&mut buff, timeout.as_millis() as i32)
-        {
-            Ok(n) => n,
-            Err(HidError::IoError { error }) if error.kind() == ErrorKind::TimedOut => {
-                return Err(Error::Timeout)
-            }
-            Err(e) => return Err(e.into()),
+        let resp = read_frames(&mut self.device, timeout, self.max_response_len)?;
+
+        debug!("RX: {:?}", Payload::full(&resp));
+
+        check_response(&resp)?;
+
+        Ok(resp)
+    }
+
+    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
+        Ok(self.device.get_device_info().is_ok())
+    }
+}
+
+/// Maximum delay between packets of a multi-packet response
+const HID_FRAME_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// HID packet source, abstracted for testing
+trait HidRead {
+    /// Read a packet into `buff`, returning zero bytes if `timeout_ms` elapses
+    fn read_timeout(&mut self, buff: &mut [u8], timeout_ms: i32) -> Result<usize, HidError>;
+}
+
+impl HidRead for HidDevice {
+    fn read_timeout(&mut self, buff: &mut [u8], timeout_ms: i32) -> Result<usize, HidError> {
+        HidDevice::read_timeout(self, buff, timeout_ms)
+    }
+}
+
+/// Helper to reassemble a response from HID packets
+///
+/// `timeout` bounds the complete response, with continuation packets additionally
+/// bounded by [HID_FRAME_TIMEOUT], returning [Error::Timeout] if either elapses.
+fn read_frames(d: &mut impl HidRead, timeout: Duration, max_len: usize) -> Result<Vec<u8>, Error> {
+    let deadline = Instant::now() + timeout;
+    let mut buff = [0u8; HID_PACKET_LEN + 1];
+
+    // Read first chunk of response
+    let n = match d.read_timeout(&mut buff, timeout.as_millis() as i32) {
+        Ok(n) => n,
+        Err(HidError::IoError { error }) if error.kind() == ErrorKind::TimedOut => {
+            return Err(Error::Timeout)
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Check read length is valid for following operations
+    if n == 0 {
+        error!("Empty response");
+        return Err(Error::EmptyResponse);
+    } else if n < 7 {
+        error!("Unexpected read length {n}");
+        return Err(Error::UnexpectedResponse);
+    }
+
+    // Check header matches expectations
+    if buff[..5] != [0x01, 0x01, 0x05, 0x00, 0x00] {
+        error!("Unexpected response header: {:02x?}", &buff[..5]);
+        return Err(Error::UnexpectedResponse);
+    }
+
+    trace!("initial read: {:?}", Payload::full(&buff));
+
+    // Parse response length
+    let len = u16::from_be_bytes([buff[5], buff[6]]) as usize;
+
+    trace!("Read len: {len}");
+
+    // Check length is reasonable prior to allocation
+    if len > max_len {
+        error!("Response length {len} exceeds maximum");
+        return Err(Error::ResponseTooLarge { len, max: max_len });
+    }
+
+    // Setup response buffer and add any remaining data
+    let mut resp = Vec::with_capacity(len);
+
+    let data_len = len.min(n - 7);
+    resp.extend_from_slice(&buff[7..][..data_len]);
+
+    // Read following chunks if required
+    let mut seq_idx = 1;
+    while resp.len() < len {
+        let rem = len - resp.len();
+
+        trace!("Read chunk {seq_idx} ({rem} bytes remaining)");
+
+        // Read next chunk, bounded by the frame timeout and remaining exchange time
+        let t = deadline
+            .saturating_duration_since(Instant::now())
+            .min(HID_FRAME_TIMEOUT);
+        let n = match t.is_zero() {
+            true => 0,
+            false => d.read_timeout(&mut buff, t.as_millis().max(1) as i32)?,
         };
 
-        // Check read length is valid for following operations
         if n == 0 {
-            error!("Empty response");
-            return Err(Error::EmptyResponse);
-        } else if n < 7 {
-            error!("Unexpected read length {n}");
+            warn!(
+                "Timeout awaiting continuation packet ({} of {len} bytes)",
+                resp.len()
+            );
+            return Err(Error::Timeout);
+        } else if n < 5 {
+            error!("Invalid chunk length {n}");
             return Err(Error::UnexpectedResponse);
         }
 
-        // Check header matches expectations
-        if buff[..5] != [0x01, 0x01, 0x05, 0x00, 0x00] {
+        // Check header and sequence index
+        if buff[..3] != [0x01, 0x01, 0x05] {
             error!("Unexpected response header: {:02x?}", &buff[..5]);
             return Err(Error::UnexpectedResponse);
         }
+        if u16::from_be_bytes([buff[3], buff[4]]) != seq_idx {
+            error!("Unexpected sequence index: {:02x?}", &buff[5..7]);
+            return Err(Error::UnexpectedResponse);
+        }
+
+        // Add to response buffer
+        let data_len = rem.min(n - 5);
+        resp.extend_from_slice(&buff[5..][..data_len]);
+        seq_idx += 1;
+    }
+
+    Ok(resp)
+}
+
+/// [Exchange] impl for sending APDUs to a [UsbDevice]
+#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+impl Exchange for UsbDevice {
+    async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        let metrics = self.metrics.clone();
 
-        trace!("initial read: {buff:02x?}");
+        with_metrics(&metrics, ConnType::Usb, command, async {
+            log_apdu(&self.logger, Direction::Tx, command);
 
-        // Parse response length
-        let len = u16::from_be_bytes([buff[5], buff[6]]) as usize;
+            // Write APDU command, chunked for HID transport
+            self.write(command)?;
+            // Read APDU response, chunked for HID transport
+            let resp = self.read(timeout)?;
 
-        trace!("Read len: {len}");
+            log_apdu(&self.logger, Direction::Rx, &resp);
 
-        // Setup response buffer and add any remaining data
-        let mut resp = Vec::with_capacity(len);
+            Ok(resp)
+        })
+        .await
+    }
 
-        let data_len = len.min(n - 7);
-        resp.extend_from_slice(&buff[7..][..data_len]);
+    /// HID packets carry up to 59 bytes of payload (64 byte packets less the 5 byte header)
+    fn max_payload_chunk(&self) -> usize {
+        HID_PACKET_LEN - HID_HEADER_LEN
+    }
 
-        // Read following chunks if required
-        let mut seq_idx = 1;
-        while resp.len() < len {
-            let rem = len - resp.len();
+    /// HID reads are blocking so cannot be interrupted, `cancel` is only checked
+    /// prior to sending the request
+    async fn exchange_cancellable(
+        &mut self,
+        command: &[u8],
+        timeout: Duration,
+        cancel: CancellationToken,
+    ) -> Result<Vec<u8>, Error> {
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
 
-            trace!("Read chunk {seq_idx} ({rem} bytes remaining)");
+        self.exchange(command, timeout).await
+    }
+}
 
-            // Read next chunk, constant timeout as chunks should be sent end-to-end
-            let n = match self.device.read_timeout(&mut buff, 500) {
-                Ok(n) => n,
-                Err(e) => return Err(e.into()),
-            };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            if n < 5 {
-                error!("Invalid chunk length {n}");
-                return Err(Error::UnexpectedResponse);
-            }
+    #[test]
+    fn usb_filters() {
+        let f = UsbFilters::default();
+        assert!(f.matches(LEDGER_VID, 0x4011));
+        assert!(!f.matches(0x1234, 0x4011));
 
-            // Check header and sequence index
-            if buff[..3] != [0x01, 0x01, 0x05] {
-                error!("Unexpected response header: {:02x?}", &buff[..5]);
-                return Err(Error::UnexpectedResponse);
-            }
-            if u16::from_be_bytes([buff[3], buff[4]]) != seq_idx {
-                error!("Unexpected sequence index: {:02x?}", &buff[5..7]);
-                return Err(Error::UnexpectedResponse);
+        let f = UsbFilters {
+            vendor_id: None,
+            product_ids: Some(vec![0x0001]),
+            ..Default::default()
+        };
+        assert!(f.matches(0x1234, 0x0001));
+        assert!(!f.matches(LEDGER_VID, 0x4011));
+    }
+
+    #[test]
+    fn hotplug_events() {
+        let info = |pid| LedgerInfo {
+            model: Model::from_pid(pid),
+            label: None,
+            conn: UsbInfo {
+                vid: LEDGER_VID,
+                pid,
+                path: Some(format!("path-{pid:04x}")),
+                serial: None,
             }
+            .into(),
+        };
+        let (a, b, c) = (info(0x4011), info(0x5011), info(0x6011));
+
+        assert_eq!(
+            hotplug_diff(&[], std::slice::from_ref(&a)),
+            vec![(HotplugEvent::Arrived, a.clone())]
+        );
+        assert_eq!(
+            hotplug_diff(&[a.clone(), b.clone()], &[b.clone(), c.clone()]),
+            vec![(HotplugEvent::Left, a.clone()), (HotplugEvent::Arrived, c)]
+        );
+        assert!(hotplug_diff(&[a.clone(), b.clone()], &[b, a]).is_empty());
+    }
 
-            // Add to response buffer
-            let data_len = rem.min(n - 5);
-            resp.extend_from_slice(&buff[5..][..data_len]);
-            seq_idx += 1;
-        }
+    #[test]
+    fn usb_interface_filters() {
+        // Default selects the generic interface by usage page, not the U2F interface
+        let f = UsbFilters::default();
+        assert!(f.matches_interface(LEDGER_USAGE_PAGE, 0));
+        assert!(!f.matches_interface(0xf1d0, 1));
+
+        // Backends without usage page reporting fallback to interface 0
+        assert!(f.matches_interface(0, 0));
+        assert!(!f.matches_interface(0, 1));
+
+        // Explicit interface overrides usage page
+        let f = UsbFilters {
+            interface: Some(1),
+            ..Default::default()
+        };
+        assert!(f.matches_interface(0xf1d0, 1));
+        assert!(!f.matches_interface(LEDGER_USAGE_PAGE, 0));
+    }
 
-        debug!("RX: {:02x?}", resp);
+    /// Mock HID device, returning queued packets then timing out
+    struct MockHid(VecDeque<Vec<u8>>);
 
-        Ok(resp)
+    impl HidRead for MockHid {
+        fn read_timeout(&mut self, buff: &mut [u8], _timeout_ms: i32) -> Result<usize, HidError> {
+            match self.0.pop_front() {
+                Some(p) => {
+                    buff[..p.len()].copy_from_slice(&p);
+                    Ok(p.len())
+                }
+                None => Ok(0),
+            }
+        }
     }
 
-    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
-        Ok(self.device.get_device_info().is_ok())
+    /// Build a HID response packet with the provided sequence index and data
+    fn packet(seq: u16, data: &[u8]) -> Vec<u8> {
+        let mut p = vec![0x01, 0x01, 0x05];
+        p.extend_from_slice(&seq.to_be_bytes());
+        p.extend_from_slice(data);
+        p.resize(HID_PACKET_LEN, 0);
+        p
     }
-}
 
-/// [Exchange] impl for sending APDUs to a [UsbDevice]
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
-impl Exchange for UsbDevice {
-    async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
-        // Write APDU command, chunked for HID transport
-        self.write(command)?;
-        // Read APDU response, chunked for HID transport
-        self.read(timeout)
+    #[test]
+    fn read_multi_packet() {
+        let data: Vec<u8> = (0..100).collect();
+
+        let mut first = vec![0x00, data.len() as u8];
+        first.extend_from_slice(&data[..57]);
+        let mut d = MockHid(VecDeque::from([packet(0, &first), packet(1, &data[57..])]));
+
+        let r = read_frames(&mut d, Duration::from_secs(1), DEFAULT_MAX_RESPONSE_LEN).unwrap();
+        assert_eq!(r, data);
+    }
+
+    #[test]
+    fn read_stalled_device() {
+        // Header packet only, device stops sending
+        let mut d = MockHid(VecDeque::from([packet(0, &[0x00, 0x64, 0x01, 0x02])]));
+
+        let r = read_frames(&mut d, Duration::from_secs(1), DEFAULT_MAX_RESPONSE_LEN);
+        assert!(matches!(r, Err(Error::Timeout)));
+
+        // Declared length exceeds the configured maximum
+        let mut d = MockHid(VecDeque::from([packet(0, &[0x10, 0x01])]));
+
+        let r = read_frames(&mut d, Duration::from_secs(1), DEFAULT_MAX_RESPONSE_LEN);
+        assert!(matches!(
+            r,
+            Err(Error::ResponseTooLarge { len: 0x1001, .. })
+        ));
     }
 }
@@ -0,0 +1,71 @@
+//! Integration tests against a running [Speculos](https://github.com/LedgerHQ/speculos)
+//! instance using the TCP transport
+//!
+//! These are gated by the `speculos` feature and expect Speculos to already be running
+//! (for example via `ledger-sim` or `speculos.py --apdu-port 1237 ...`):
+//!
+//! ```text
+//! SPECULOS_HOST=127.0.0.1 SPECULOS_APDU_PORT=1237 \
+//!   cargo test -p ledger-lib --features speculos --test speculos
+//! ```
+//!
+//! - `SPECULOS_HOST` sets the speculos host address (defaults to `127.0.0.1`)
+//! - `SPECULOS_APDU_PORT` sets the speculos APDU port (defaults to `1237`)
+//!
+//! Tests requiring speculos at the dashboard rather than in an app are ignored by
+//! default, run these with `--ignored`.
+
+#![cfg(feature = "speculos")]
+
+use std::{net::SocketAddr, time::Duration};
+
+use ledger_lib::{
+    info::Context,
+    transport::{TcpInfo, TcpTransport},
+    Device, Transport,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Load speculos address from the environment
+fn speculos_info() -> TcpInfo {
+    let host = std::env::var("SPECULOS_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let port = std::env::var("SPECULOS_APDU_PORT").unwrap_or_else(|_| "1237".to_string());
+
+    let addr: SocketAddr = format!("{host}:{port}")
+        .parse()
+        .expect("invalid SPECULOS_HOST / SPECULOS_APDU_PORT");
+
+    TcpInfo { addr }
+}
+
+#[tokio::test]
+async fn speculos_app_info() {
+    let mut t = TcpTransport::new().unwrap();
+    let mut d = t.connect(speculos_info()).await.unwrap();
+
+    let i = d.app_info(TIMEOUT).await.unwrap();
+
+    assert!(!i.name.is_empty());
+    assert!(!i.version.is_empty());
+}
+
+/// Device info is only available from the dashboard, while speculos runs an app
+/// (and exits with it) so this must be run explicitly against a dashboard instance
+#[tokio::test]
+#[ignore = "requires speculos at the dashboard, run with `--ignored`"]
+async fn speculos_device_info() {
+    let mut t = TcpTransport::new().unwrap();
+    let mut d = t.connect(speculos_info()).await.unwrap();
+
+    assert_eq!(
+        d.current_context(TIMEOUT).await.unwrap(),
+        Context::Dashboard,
+        "device info requires speculos at the dashboard"
+    );
+
+    let i = d.device_info(TIMEOUT).await.unwrap();
+
+    assert_ne!(i.target_id, [0u8; 4]);
+    assert!(!i.se_version.is_empty());
+}
@@ -2,6 +2,7 @@
 
 use encdec::{Decode, Encode};
 
+use crate::parse::{read_bytes, read_len_prefixed, read_len_prefixed_string};
 use crate::{ApduError, ApduStatic};
 
 /// Application information request APDU
@@ -30,8 +31,18 @@ pub struct AppInfoResp<'a> {
 }
 
 bitflags::bitflags! {
-    /// Application info flags
-    #[derive(Clone, Debug, PartialEq)]
+    /// Application info flags, as reported by the dashboard / running application
+    ///
+    /// | Bit | Flag                | Meaning                                   |
+    /// |-----|---------------------|-------------------------------------------|
+    /// | 0   | [AppFlags::RECOVERY]        | Device is in recovery mode        |
+    /// | 1   | [AppFlags::SIGNED]          | Application is signed by Ledger   |
+    /// | 2   | [AppFlags::ONBOARDED]       | Device onboarding is complete     |
+    /// | 3   | [AppFlags::TRUST_ISSUER]    | Issuer trusted                    |
+    /// | 4   | [AppFlags::TRUST_CUSTOM_CA] | Custom CA trusted                 |
+    /// | 5   | [AppFlags::HSM_INITIALISED] | HSM initialised                   |
+    /// | 7   | [AppFlags::PIN_VALIDATED]   | PIN entered, device is unlocked   |
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
     #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct AppFlags: u8 {
         /// Recovery mode
@@ -51,6 +62,28 @@ bitflags::bitflags! {
     }
 }
 
+impl AppFlags {
+    /// Check whether the PIN has been validated (ie. the device is unlocked)
+    pub fn is_pin_validated(&self) -> bool {
+        self.contains(Self::PIN_VALIDATED)
+    }
+
+    /// Check whether device onboarding has been completed
+    pub fn is_onboarded(&self) -> bool {
+        self.contains(Self::ONBOARDED)
+    }
+
+    /// Check whether the device is in recovery mode
+    pub fn is_recovery_mode(&self) -> bool {
+        self.contains(Self::RECOVERY)
+    }
+
+    /// Check whether the running application is signed
+    pub fn is_signed(&self) -> bool {
+        self.contains(Self::SIGNED)
+    }
+}
+
 impl<'a> AppInfoResp<'a> {
     /// Create a new application version APDU
     pub fn new(name: &'a str, version: &'a str, flags: AppFlags) -> Self {
@@ -109,32 +142,26 @@ impl<'a> Decode<'a> for AppInfoResp<'a> {
     type Error = ApduError;
 
     fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
-        let mut index = 0;
-
         // Check app version format
-        if buff[index] != APP_VERSION_FMT {
-            return Err(ApduError::InvalidVersion(buff[index]));
+        let (fmt, index) = read_bytes(buff, 0, 1)?;
+        if fmt[0] != APP_VERSION_FMT {
+            return Err(ApduError::InvalidVersion(fmt[0]));
         }
-        index += 1;
 
         // Fetch name string
-        let name_len = buff[index] as usize;
-        let name = core::str::from_utf8(&buff[index + 1..][..name_len])
-            .map_err(|_| ApduError::InvalidUtf8)?;
-        index += 1 + name_len;
+        let (name, index) = read_len_prefixed_string(buff, index)?;
 
         // Fetch version string
-        let version_len = buff[index] as usize;
-        let version = core::str::from_utf8(&buff[index + 1..][..version_len])
-            .map_err(|_| ApduError::InvalidUtf8)?;
-        index += 1 + version_len;
+        let (version, mut index) = read_len_prefixed_string(buff, index)?;
 
         // Fetch flags (if available)
         let flags = if buff.len() > index {
-            let flags_len = buff[index];
-            let flags = AppFlags::from_bits_truncate(buff[index + 1]);
-            index += 1 + flags_len as usize;
-            flags
+            let (flags, i) = read_len_prefixed(buff, index)?;
+            if flags.is_empty() {
+                return Err(ApduError::InvalidLength);
+            }
+            index = i;
+            AppFlags::from_bits_truncate(flags[0])
         } else {
             AppFlags::empty()
         };
@@ -161,4 +188,35 @@ mod tests {
         let mut buff = [0u8; 256];
         crate::tests::encode_decode(&mut buff, r);
     }
+
+    #[test]
+    fn app_info_resp_truncated() {
+        let r = AppInfoResp::new("test name", "test version", AppFlags::ONBOARDED);
+
+        let mut buff = [0u8; 256];
+        let n = r.encode(&mut buff).unwrap();
+
+        // Flags are optional so decoding without these (n - 2) is valid
+        for i in (0..n).filter(|i| *i != n - 2) {
+            assert!(
+                AppInfoResp::decode(&buff[..i]).is_err(),
+                "decode should fail with {i} of {n} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn app_flags() {
+        let f = AppFlags::from_bits_truncate(0b1000_0100);
+        assert!(f.is_pin_validated());
+        assert!(f.is_onboarded());
+        assert!(!f.is_recovery_mode());
+        assert!(!f.is_signed());
+
+        let f = AppFlags::from_bits_truncate(0b0000_0011);
+        assert!(!f.is_pin_validated());
+        assert!(!f.is_onboarded());
+        assert!(f.is_recovery_mode());
+        assert!(f.is_signed());
+    }
 }
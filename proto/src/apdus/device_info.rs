@@ -2,6 +2,7 @@
 
 use encdec::{Decode, Encode};
 
+use crate::parse::{read_bytes, read_len_prefixed, read_len_prefixed_string};
 use crate::{ApduError, ApduStatic};
 
 /// Device info APDU command
@@ -102,29 +103,19 @@ impl<'a> Decode<'a> for DeviceInfoResp<'a> {
 
     /// Decode an device info APDU from the provided buffer
     fn decode(buff: &'a [u8]) -> Result<(Self, usize), ApduError> {
-        let mut index = 0;
-
-        // Fetch target id
+        // Fetch target id (retained as raw bytes)
+        let (id, index) = read_bytes(buff, 0, 4)?;
         let mut target_id = [0u8; 4];
-        target_id.copy_from_slice(&buff[..4]);
-        index += 4;
+        target_id.copy_from_slice(id);
 
         // Fetch secure element version
-        let se_version_len = buff[index] as usize;
-        let se_version = core::str::from_utf8(&buff[index + 1..][..se_version_len])
-            .map_err(|_| ApduError::InvalidUtf8)?;
-        index += 1 + se_version_len;
+        let (se_version, index) = read_len_prefixed_string(buff, index)?;
 
         // Fetch flags
-        let flags_len = buff[index] as usize;
-        let flags = &buff[index + 1..][..flags_len];
-        index += 1 + flags_len;
+        let (flags, index) = read_len_prefixed(buff, index)?;
 
         // Fetch mcu version
-        let mcu_version_len = buff[index] as usize;
-        let mcu_version = core::str::from_utf8(&buff[index + 1..][..mcu_version_len])
-            .map_err(|_| ApduError::InvalidUtf8)?;
-        index += 1 + mcu_version_len;
+        let (mcu_version, index) = read_len_prefixed_string(buff, index)?;
 
         Ok((
             Self {
@@ -149,4 +140,19 @@ mod tests {
         let mut buff = [0u8; 256];
         crate::tests::encode_decode(&mut buff, r);
     }
+
+    #[test]
+    fn device_info_resp_truncated() {
+        let r = DeviceInfoResp::new([0x01, 0x02, 0x03, 0x04], "SOME SE", "SOME MCU", &[0xaa]);
+
+        let mut buff = [0u8; 256];
+        let n = r.encode(&mut buff).unwrap();
+
+        for i in 0..n {
+            assert!(
+                DeviceInfoResp::decode(&buff[..i]).is_err(),
+                "decode should fail with {i} of {n} bytes"
+            );
+        }
+    }
 }
@@ -0,0 +1,189 @@
+//! APDU command builder and chunking helpers for large commands
+
+use alloc::vec::Vec;
+
+use crate::{ApduError, ApduHeader, GenericApdu};
+
+/// Maximum data length for short (single byte `Lc`) APDUs
+const SHORT_APDU_MAX_LEN: usize = u8::MAX as usize;
+
+/// APDU command builder, encoding header, `Lc` and data
+///
+/// ```
+/// use ledger_proto::ApduCommand;
+///
+/// let c = ApduCommand::new(0xb0, 0x01, 0x00, 0x00).data(&[0x01, 0x02]);
+/// assert_eq!(c.encode().unwrap(), vec![0xb0, 0x01, 0x00, 0x00, 0x02, 0x01, 0x02]);
+/// ```
+///
+/// Data longer than 255 bytes is encoded using extended length `Lc` (`0x00` followed
+/// by a 2-byte big endian length), note that this is not supported by all applications.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ApduCommand {
+    header: ApduHeader,
+    data: Vec<u8>,
+}
+
+impl ApduCommand {
+    /// Create a new [ApduCommand] with the provided header values and no data
+    pub fn new(cla: u8, ins: u8, p1: u8, p2: u8) -> Self {
+        Self {
+            header: ApduHeader { cla, ins, p1, p2 },
+            data: Vec::new(),
+        }
+    }
+
+    /// Set command data
+    pub fn data(mut self, data: &[u8]) -> Self {
+        self.data = data.to_vec();
+        self
+    }
+
+    /// Fetch the command header
+    pub fn header(&self) -> ApduHeader {
+        self.header
+    }
+
+    /// Encode command to bytes, returning [ApduError::InvalidLength] if
+    /// the data exceeds the maximum extended APDU length
+    pub fn encode(&self) -> Result<Vec<u8>, ApduError> {
+        let h = &self.header;
+        let n = self.data.len();
+
+        let mut buff = Vec::with_capacity(7 + n);
+        buff.extend_from_slice(&[h.cla, h.ins, h.p1, h.p2]);
+
+        match n {
+            0..=SHORT_APDU_MAX_LEN => buff.push(n as u8),
+            _ if n <= u16::MAX as usize => {
+                buff.push(0x00);
+                buff.extend_from_slice(&(n as u16).to_be_bytes());
+            }
+            _ => return Err(ApduError::InvalidLength),
+        }
+
+        buff.extend_from_slice(&self.data);
+
+        Ok(buff)
+    }
+}
+
+impl From<GenericApdu> for ApduCommand {
+    fn from(a: GenericApdu) -> Self {
+        Self {
+            header: a.header,
+            data: a.data,
+        }
+    }
+}
+
+impl From<ApduCommand> for GenericApdu {
+    fn from(c: ApduCommand) -> Self {
+        Self {
+            header: c.header,
+            data: c.data,
+        }
+    }
+}
+
+/// Split `data` into a sequence of encoded APDUs for commands exceeding a
+/// single APDU, using `p1_first` for the first chunk and `p1_more` for subsequent chunks
+///
+/// `chunk_size` is clamped to the short APDU data limit (255 bytes), transports may
+/// provide a preferred size to avoid transport-level fragmentation.
+/// Empty `data` results in a single APDU.
+pub fn chunk_apdu(
+    cla: u8,
+    ins: u8,
+    p1_first: u8,
+    p1_more: u8,
+    p2: u8,
+    data: &[u8],
+    chunk_size: usize,
+) -> Vec<Vec<u8>> {
+    let chunk_size = chunk_size.clamp(1, SHORT_APDU_MAX_LEN);
+
+    if data.is_empty() {
+        return alloc::vec![encode_short(cla, ins, p1_first, p2, &[])];
+    }
+
+    data.chunks(chunk_size)
+        .enumerate()
+        .map(|(i, c)| {
+            let p1 = if i == 0 { p1_first } else { p1_more };
+            encode_short(cla, ins, p1, p2, c)
+        })
+        .collect()
+}
+
+/// Helper to encode a short APDU (data length must be <= 255 bytes)
+fn encode_short(cla: u8, ins: u8, p1: u8, p2: u8, data: &[u8]) -> Vec<u8> {
+    debug_assert!(data.len() <= SHORT_APDU_MAX_LEN);
+
+    let mut buff = Vec::with_capacity(5 + data.len());
+    buff.extend_from_slice(&[cla, ins, p1, p2, data.len() as u8]);
+    buff.extend_from_slice(data);
+    buff
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn encode_commands() {
+        // No data
+        let c = ApduCommand::new(0xe0, 0x01, 0x02, 0x03);
+        assert_eq!(c.encode().unwrap(), vec![0xe0, 0x01, 0x02, 0x03, 0x00]);
+
+        // Short APDU
+        let c = c.data(&[0xaa; 255]);
+        let b = c.encode().unwrap();
+        assert_eq!(&b[..5], &[0xe0, 0x01, 0x02, 0x03, 0xff]);
+        assert_eq!(b.len(), 5 + 255);
+
+        // Extended APDU
+        let c = c.data(&[0xaa; 256]);
+        let b = c.encode().unwrap();
+        assert_eq!(&b[..7], &[0xe0, 0x01, 0x02, 0x03, 0x00, 0x01, 0x00]);
+        assert_eq!(b.len(), 7 + 256);
+
+        // Too long
+        let c = c.data(&vec![0xaa; u16::MAX as usize + 1]);
+        assert!(matches!(c.encode(), Err(ApduError::InvalidLength)));
+    }
+
+    #[test]
+    fn chunk_commands() {
+        let data: Vec<u8> = (0..10).collect();
+
+        // Exact multiple of chunk size
+        let c = chunk_apdu(0xe0, 0x04, 0x00, 0x80, 0x01, &data, 5);
+        assert_eq!(
+            c,
+            vec![
+                vec![0xe0, 0x04, 0x00, 0x01, 5, 0, 1, 2, 3, 4],
+                vec![0xe0, 0x04, 0x80, 0x01, 5, 5, 6, 7, 8, 9],
+            ]
+        );
+
+        // Trailing partial chunk
+        let c = chunk_apdu(0xe0, 0x04, 0x00, 0x80, 0x01, &data, 4);
+        assert_eq!(c.len(), 3);
+        assert_eq!(c[2], vec![0xe0, 0x04, 0x80, 0x01, 2, 8, 9]);
+
+        // Single chunk and empty data
+        let c = chunk_apdu(0xe0, 0x04, 0x00, 0x80, 0x01, &data, 255);
+        assert_eq!(c.len(), 1);
+        let c = chunk_apdu(0xe0, 0x04, 0x00, 0x80, 0x01, &[], 255);
+        assert_eq!(c, vec![vec![0xe0, 0x04, 0x00, 0x01, 0]]);
+
+        // Chunk size is clamped to the short APDU limit
+        let c = chunk_apdu(0xe0, 0x04, 0x00, 0x80, 0x01, &[0xaa; 300], 1024);
+        assert_eq!(c.len(), 2);
+        assert_eq!(c[0][4], 255);
+        assert_eq!(c[1][4], 45);
+    }
+}
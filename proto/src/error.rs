@@ -15,6 +15,9 @@ pub enum ApduError {
 
     /// Invalid APDU encoding
     InvalidEncoding,
+
+    /// Invalid derivation path
+    InvalidPath,
 }
 
 impl From<encdec::Error> for ApduError {
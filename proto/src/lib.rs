@@ -119,9 +119,24 @@ pub use error::ApduError;
 
 pub mod apdus;
 
+pub mod parse;
+
 mod status;
 pub use status::StatusCode;
 
+mod model;
+pub use model::Model;
+
+#[cfg(feature = "alloc")]
+mod command;
+#[cfg(feature = "alloc")]
+pub use command::{chunk_apdu, ApduCommand};
+
+#[cfg(feature = "alloc")]
+mod path;
+#[cfg(feature = "alloc")]
+pub use path::{encode_derivation_path, DerivationPath, HARDENED, MAX_PATH_DEPTH};
+
 /// APDU command header
 #[derive(Copy, Clone, PartialEq, Debug, Default, Encode, DecodeOwned)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -0,0 +1,183 @@
+//! Ledger device model definitions
+
+use core::{fmt::Display, str::FromStr};
+
+use crate::ApduError;
+
+/// Ledger device models
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Model {
+    /// Nano S
+    NanoS,
+    /// Nano S Plus
+    NanoSPlus,
+    /// Nano X
+    NanoX,
+    /// Stax
+    Stax,
+    /// Flex
+    Flex,
+    /// Unknown model
+    Unknown(u16),
+}
+
+impl Model {
+    /// Convert a USB PID to a [Model] kind
+    ///
+    /// Note that ledger PIDs vary depending on the device state so only the top byte is used
+    /// for matching.
+    pub fn from_pid(pid: u16) -> Model {
+        match pid & 0xFF00 {
+            // TODO: support all the models
+            //0x0001 => Ok(Model::NanoS),
+            0x4000 => Model::NanoX,
+            0x5000 => Model::NanoSPlus,
+            //0x0006 => Ok(Model::Stax),
+            _ => Model::Unknown(pid),
+        }
+    }
+
+    /// Convert a target ID (as reported in [DeviceInfoResp](crate::apdus::DeviceInfoResp))
+    /// to a [Model] kind
+    ///
+    /// The low byte varies with firmware version so is ignored for matching,
+    /// unrecognised target IDs return [Model::Unknown] with a zero PID.
+    pub fn from_target_id(target_id: &[u8; 4]) -> Model {
+        match u32::from_be_bytes(*target_id) & 0xFFFF_FF00 {
+            0x3110_0000 => Model::NanoS,
+            0x3300_0000 => Model::NanoX,
+            0x3310_0000 => Model::NanoSPlus,
+            0x3320_0000 => Model::Stax,
+            0x3330_0000 => Model::Flex,
+            _ => Model::Unknown(0),
+        }
+    }
+
+    /// Fetch the target ID for a [Model], the inverse of [Model::from_target_id]
+    ///
+    /// This returns the target ID reported by current firmware, or `None` for unknown models.
+    pub fn target_id(&self) -> Option<[u8; 4]> {
+        let id: u32 = match self {
+            Model::NanoS => 0x3110_0004,
+            Model::NanoX => 0x3300_0004,
+            Model::NanoSPlus => 0x3310_0004,
+            Model::Stax => 0x3320_0004,
+            Model::Flex => 0x3330_0004,
+            Model::Unknown(_) => return None,
+        };
+        Some(id.to_be_bytes())
+    }
+
+    /// Check whether the device has a touchscreen (Stax, Flex)
+    pub fn is_touchscreen(&self) -> bool {
+        matches!(self, Model::Stax | Model::Flex)
+    }
+
+    /// Check whether the device uses physical buttons for input (Nano devices)
+    pub fn has_buttons(&self) -> bool {
+        matches!(self, Model::NanoS | Model::NanoSPlus | Model::NanoX)
+    }
+
+    /// Check whether the device supports bluetooth connections
+    pub fn supports_ble(&self) -> bool {
+        matches!(self, Model::NanoX | Model::Stax | Model::Flex)
+    }
+}
+
+impl Display for Model {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let s = match self {
+            Model::NanoS => "NanoS",
+            Model::NanoSPlus => "NanoSPlus",
+            Model::NanoX => "NanoX",
+            Model::Stax => "Stax",
+            Model::Flex => "Flex",
+            Model::Unknown(_) => "Unknown",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Parse a [Model] by name, as output by [Display]
+impl FromStr for Model {
+    type Err = ApduError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NanoS" => Ok(Model::NanoS),
+            "NanoSPlus" => Ok(Model::NanoSPlus),
+            "NanoX" => Ok(Model::NanoX),
+            "Stax" => Ok(Model::Stax),
+            "Flex" => Ok(Model::Flex),
+            "Unknown" => Ok(Model::Unknown(0)),
+            _ => Err(ApduError::InvalidEncoding),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn model_capabilities() {
+        let tests = [
+            (Model::NanoS, false, true, false),
+            (Model::NanoSPlus, false, true, false),
+            (Model::NanoX, false, true, true),
+            (Model::Stax, true, false, true),
+            (Model::Flex, true, false, true),
+            (Model::Unknown(0), false, false, false),
+        ];
+
+        for (m, touch, buttons, ble) in tests {
+            assert_eq!(m.is_touchscreen(), touch, "{m} touchscreen");
+            assert_eq!(m.has_buttons(), buttons, "{m} buttons");
+            assert_eq!(m.supports_ble(), ble, "{m} ble");
+        }
+    }
+
+    #[test]
+    fn model_target_ids() {
+        for m in [
+            Model::NanoS,
+            Model::NanoSPlus,
+            Model::NanoX,
+            Model::Stax,
+            Model::Flex,
+        ] {
+            let id = m.target_id().unwrap();
+            assert_eq!(Model::from_target_id(&id), m);
+        }
+
+        assert_eq!(
+            Model::from_target_id(&[0x33, 0x00, 0x00, 0x04]),
+            Model::NanoX
+        );
+        assert_eq!(
+            Model::from_target_id(&[0x33, 0x20, 0x00, 0x04]),
+            Model::Stax
+        );
+        assert_eq!(
+            Model::from_target_id(&[0x31, 0x10, 0x00, 0x02]),
+            Model::NanoS
+        );
+        assert_eq!(Model::from_target_id(&[0x00; 4]), Model::Unknown(0));
+        assert_eq!(Model::Unknown(0).target_id(), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn model_names() {
+        for m in [
+            Model::NanoS,
+            Model::NanoSPlus,
+            Model::NanoX,
+            Model::Stax,
+            Model::Flex,
+        ] {
+            assert_eq!(Model::from_str(&alloc::format!("{m}")).unwrap(), m);
+        }
+    }
+}
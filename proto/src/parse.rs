@@ -0,0 +1,98 @@
+//! Bounds-checked helpers for parsing APDU response data
+//!
+//! Each helper reads from `buff` at `index`, returning the parsed value and the
+//! index following it, or [ApduError::InvalidLength] where `buff` is truncated.
+//! Multi-byte integers in Ledger APDUs are big-endian.
+
+use crate::ApduError;
+
+/// Read `n` bytes at `index`
+///
+/// ```
+/// use ledger_proto::parse::read_bytes;
+///
+/// let (target_id, index) = read_bytes(&[0x33, 0x00, 0x00, 0x04, 0x05], 0, 4).unwrap();
+/// assert_eq!(target_id, &[0x33, 0x00, 0x00, 0x04]);
+/// assert_eq!(index, 4);
+/// ```
+pub fn read_bytes(buff: &[u8], index: usize, n: usize) -> Result<(&[u8], usize), ApduError> {
+    match buff.get(index..).and_then(|b| b.get(..n)) {
+        Some(b) => Ok((b, index + n)),
+        None => Err(ApduError::InvalidLength),
+    }
+}
+
+/// Read a big-endian `u16` at `index`
+///
+/// ```
+/// use ledger_proto::parse::read_u16_be;
+///
+/// assert_eq!(read_u16_be(&[0x90, 0x00], 0).unwrap(), (0x9000, 2));
+/// assert!(read_u16_be(&[0x90], 0).is_err());
+/// ```
+pub fn read_u16_be(buff: &[u8], index: usize) -> Result<(u16, usize), ApduError> {
+    let (b, index) = read_bytes(buff, index, 2)?;
+    Ok((u16::from_be_bytes([b[0], b[1]]), index))
+}
+
+/// Read a single-byte length-prefixed field at `index`
+///
+/// ```
+/// use ledger_proto::parse::read_len_prefixed;
+///
+/// assert_eq!(read_len_prefixed(&[0x02, 0xaa, 0xbb, 0xcc], 0).unwrap(), (&[0xaa, 0xbb][..], 3));
+/// ```
+pub fn read_len_prefixed(buff: &[u8], index: usize) -> Result<(&[u8], usize), ApduError> {
+    let (len, index) = read_bytes(buff, index, 1)?;
+    read_bytes(buff, index, len[0] as usize)
+}
+
+/// Read a single-byte length-prefixed UTF-8 string at `index`
+///
+/// ```
+/// use ledger_proto::parse::read_len_prefixed_string;
+///
+/// let buff = [0x05, b'B', b'O', b'L', b'O', b'S'];
+/// assert_eq!(read_len_prefixed_string(&buff, 0).unwrap(), ("BOLOS", 6));
+/// ```
+pub fn read_len_prefixed_string(buff: &[u8], index: usize) -> Result<(&str, usize), ApduError> {
+    let (b, index) = read_len_prefixed(buff, index)?;
+    let s = core::str::from_utf8(b).map_err(|_| ApduError::InvalidUtf8)?;
+    Ok((s, index))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn truncated_inputs() {
+        assert!(matches!(
+            read_bytes(&[0x01], 1, 1),
+            Err(ApduError::InvalidLength)
+        ));
+        assert!(matches!(
+            read_bytes(&[0x01], 2, 0),
+            Err(ApduError::InvalidLength)
+        ));
+        assert!(matches!(
+            read_u16_be(&[0x01, 0x02, 0x03], 2),
+            Err(ApduError::InvalidLength)
+        ));
+
+        assert!(matches!(
+            read_len_prefixed(&[], 0),
+            Err(ApduError::InvalidLength)
+        ));
+        assert!(matches!(
+            read_len_prefixed(&[0x03, 0x01, 0x02], 0),
+            Err(ApduError::InvalidLength)
+        ));
+        assert_eq!(read_len_prefixed(&[0x00], 0).unwrap(), (&[][..], 1));
+
+        assert!(matches!(
+            read_len_prefixed_string(&[0x02, 0xff, 0xfe], 0),
+            Err(ApduError::InvalidUtf8)
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+//! BIP-32 derivation path type and encoding helpers
+
+use alloc::vec::Vec;
+use core::{fmt::Display, str::FromStr};
+
+use encdec::{DecodeOwned, Encode};
+
+use crate::ApduError;
+
+/// Hardened derivation index flag
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// Maximum derivation path depth supported by Ledger devices
+pub const MAX_PATH_DEPTH: usize = 10;
+
+/// BIP-32 derivation path, encoded as a length-prefixed array of big-endian `u32`s
+///
+/// ```
+/// use ledger_proto::{DerivationPath, HARDENED};
+///
+/// let p: DerivationPath = "m/44'/60'/0'/0/0".parse().unwrap();
+/// assert_eq!(p.components(), &[44 | HARDENED, 60 | HARDENED, HARDENED, 0, 0]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Create a derivation path from raw components (including [HARDENED] flags)
+    pub fn new(components: Vec<u32>) -> Self {
+        Self(components)
+    }
+
+    /// Fetch raw path components
+    pub fn components(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+/// Parse a derivation path from `m/44'/60'/0'/0/0` style strings,
+/// accepting `'`, `h` or `H` as hardened markers
+impl FromStr for DerivationPath {
+    type Err = ApduError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        // Root path
+        if s.is_empty() || s == "m" || s == "M" {
+            return Ok(Self::default());
+        }
+
+        let s = s
+            .strip_prefix("m/")
+            .or_else(|| s.strip_prefix("M/"))
+            .unwrap_or(s);
+
+        let mut components = Vec::new();
+
+        for c in s.split('/') {
+            let (c, hardened) = match c.strip_suffix(['\'', 'h', 'H']) {
+                Some(v) => (v, true),
+                None => (c, false),
+            };
+
+            let i: u32 = c.parse().map_err(|_| ApduError::InvalidPath)?;
+            if i & HARDENED != 0 {
+                return Err(ApduError::InvalidPath);
+            }
+
+            components.push(if hardened { i | HARDENED } else { i });
+        }
+
+        if components.len() > MAX_PATH_DEPTH {
+            return Err(ApduError::InvalidPath);
+        }
+
+        Ok(Self(components))
+    }
+}
+
+impl Display for DerivationPath {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "m")?;
+
+        for c in &self.0 {
+            match c & HARDENED != 0 {
+                true => write!(f, "/{}'", c & !HARDENED)?,
+                false => write!(f, "/{c}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Encode for DerivationPath {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1 + self.0.len() * 4)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if self.0.len() > MAX_PATH_DEPTH || buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        // Write path depth
+        buff[0] = self.0.len() as u8;
+
+        // Write components
+        for (i, c) in self.0.iter().enumerate() {
+            buff[1 + i * 4..][..4].copy_from_slice(&c.to_be_bytes());
+        }
+
+        Ok(n)
+    }
+}
+
+impl DecodeOwned for DerivationPath {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode_owned(buff: &[u8]) -> Result<(Self::Output, usize), Self::Error> {
+        // Read path depth
+        let depth = *buff.first().ok_or(ApduError::InvalidLength)? as usize;
+        let n = 1 + depth * 4;
+        if depth > MAX_PATH_DEPTH || buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        // Read components
+        let components = buff[1..n]
+            .chunks(4)
+            .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+
+        Ok((Self(components), n))
+    }
+}
+
+/// Parse and encode a derivation path string (see [DerivationPath])
+pub fn encode_derivation_path(path: &str) -> Result<Vec<u8>, ApduError> {
+    let p = DerivationPath::from_str(path)?;
+
+    let mut buff = alloc::vec![0u8; p.encode_len()?];
+    p.encode(&mut buff)?;
+
+    Ok(buff)
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn parse_paths() {
+        let p: DerivationPath = "m/44'/60'/0'/0/0".parse().unwrap();
+        assert_eq!(
+            p.components(),
+            &[44 | HARDENED, 60 | HARDENED, HARDENED, 0, 0]
+        );
+        assert_eq!(p.to_string(), "m/44'/60'/0'/0/0");
+
+        // Alternate hardened markers and no prefix
+        let p2: DerivationPath = "44h/60H/0'/0/0".parse().unwrap();
+        assert_eq!(p, p2);
+
+        // Root path
+        let p: DerivationPath = "m".parse().unwrap();
+        assert!(p.components().is_empty());
+        assert_eq!(p.to_string(), "m");
+
+        // Invalid paths
+        for s in ["m/44'/x", "m//0", "m/2147483648", "m/0/0/0/0/0/0/0/0/0/0/0"] {
+            assert!(DerivationPath::from_str(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn encode_decode_path() {
+        let b = encode_derivation_path("m/44'/60'/0'/0/1").unwrap();
+        assert_eq!(
+            b,
+            &[
+                5, 0x80, 0x00, 0x00, 0x2c, 0x80, 0x00, 0x00, 0x3c, 0x80, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01
+            ]
+        );
+
+        let p: DerivationPath = "m/44'/60'/0'/0/1".parse().unwrap();
+        let mut buff = [0u8; 64];
+        crate::tests::encode_decode(&mut buff, p);
+    }
+}
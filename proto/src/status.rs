@@ -1,7 +1,9 @@
 /// Device status codes (two bytes, trailing response data)
 ///
 /// Replicated from: https://github.com/LedgerHQ/ledger-live/blob/develop/libs/ledgerjs/packages/errors/src/index.ts#L212
-#[derive(Copy, Clone, Debug, displaydoc::Display, num_enum::TryFromPrimitive)]
+#[derive(
+    Copy, Clone, PartialEq, Eq, Hash, Debug, displaydoc::Display, num_enum::TryFromPrimitive,
+)]
 #[repr(u16)]
 pub enum StatusCode {
     /// Access condition not fulfilled
@@ -83,3 +85,32 @@ pub enum StatusCode {
     /// Not enough space
     NotEnoughSpace = 0x5102,
 }
+
+impl StatusCode {
+    /// Match a status word, returning `None` for unrecognised values
+    pub fn from_u16(v: u16) -> Option<Self> {
+        Self::try_from(v).ok()
+    }
+
+    /// Fetch the numeric status word
+    pub fn code(&self) -> u16 {
+        *self as u16
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn status_codes() {
+        assert_eq!(StatusCode::from_u16(0x5515), Some(StatusCode::LockedDevice));
+        assert_eq!(
+            StatusCode::from_u16(0x6e00),
+            Some(StatusCode::ClaNotSupported)
+        );
+        assert_eq!(StatusCode::from_u16(0x1234), None);
+
+        assert_eq!(StatusCode::IncorrectLength.code(), 0x6700);
+    }
+}